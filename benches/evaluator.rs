@@ -0,0 +1,69 @@
+use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion};
+use rand::{rngs::StdRng, seq::SliceRandom, SeedableRng};
+
+use discord_texas_holdem::poker::Card;
+use discord_texas_holdem::poker::combo::{
+    evaluate, find_best_card, find_best_card_naive, find_best_hand, CardSet
+};
+
+// The same pseudo-random hands for every run, so results stay comparable.
+fn hands(size: usize, count: usize) -> Vec<Vec<Card>> {
+    let mut rng = StdRng::seed_from_u64(42);
+    let mut deck: Vec<Card> = (0..52).filter_map(Card::from_index).collect();
+    (0..count)
+        .map(|_| {
+            deck.shuffle(&mut rng);
+            deck[..size].to_vec()
+        })
+        .collect()
+}
+
+fn evaluators(c: &mut Criterion) {
+    let mut group = c.benchmark_group("find_best_card");
+    for size in [5, 6, 7].iter() {
+        let hands = hands(*size, 1000);
+        group.bench_with_input(BenchmarkId::new("table", size), &hands, |b, hands| {
+            b.iter(|| {
+                for cards in hands {
+                    black_box(find_best_card(black_box(cards)));
+                }
+            })
+        });
+        group.bench_with_input(BenchmarkId::new("naive", size), &hands, |b, hands| {
+            b.iter(|| {
+                for cards in hands {
+                    black_box(find_best_card_naive(black_box(cards)));
+                }
+            })
+        });
+    }
+    group.finish();
+}
+
+fn card_sets(c: &mut Criterion) {
+    let sets: Vec<CardSet> = hands(7, 1000)
+        .iter()
+        .map(|cards| cards.iter().collect())
+        .collect();
+    c.bench_function("evaluate 7 card sets", |b| {
+        b.iter(|| {
+            for set in &sets {
+                black_box(evaluate(black_box(*set)));
+            }
+        })
+    });
+}
+
+fn best_hand(c: &mut Criterion) {
+    let hands = hands(7, 1000);
+    c.bench_function("find_best_hand 7 cards", |b| {
+        b.iter(|| {
+            for cards in &hands {
+                black_box(find_best_hand(black_box(cards)));
+            }
+        })
+    });
+}
+
+criterion_group!(benches, evaluators, card_sets, best_hand);
+criterion_main!(benches);
@@ -0,0 +1,122 @@
+// Wallets: a!wallet, a!ledger, and a!grant and a!revoke for administrators
+use serenity::{
+    client::Context,
+    model::{
+        channel::Message,
+        id::UserId
+    },
+    prelude::Mentionable,
+    framework::standard::{
+        Args, CommandResult,
+        macros::{
+            command, group
+        }
+    }
+};
+
+use discord_texas_holdem::wallet::{Kind, Transaction};
+
+use super::wallets;
+
+#[group]
+#[only_in(guilds)]
+#[commands(wallet, ledger, grant, revoke)]
+struct Bank;
+
+fn format_transaction(t: &Transaction) -> String {
+    let sign = match t.kind {
+        Kind::Grant { .. } | Kind::CashOut { .. } => '+',
+        Kind::Revoke { .. } | Kind::BuyIn { .. } => '-',
+    };
+    format!("#{} {}{} {}, balance {}", t.id, sign, t.amount, t.kind, t.balance)
+}
+
+// a!wallet [@user]
+#[command]
+async fn wallet(ctx: &Context, msg: &Message, mut args: Args) -> CommandResult {
+    let guild = msg.guild_id.unwrap_or_default().0;
+    let user = args.single::<UserId>().unwrap_or(msg.author.id);
+
+    let response = {
+        let wallets = wallets(ctx).await;
+        let mut wallets = wallets.lock().await;
+        match wallets.get(guild) {
+            Ok(wallet) => format!("{} has {} chips", user.mention(), wallet.balance(user.0)),
+            Err(why) => {
+                println!("WALLET ERROR: {:?}", why);
+                String::from("The wallets couldn't be read")
+            }
+        }
+    };
+    msg.channel_id.say(ctx, response).await?;
+    Ok(())
+}
+
+// a!ledger [@user], the last transactions of a wallet
+#[command]
+async fn ledger(ctx: &Context, msg: &Message, mut args: Args) -> CommandResult {
+    let guild = msg.guild_id.unwrap_or_default().0;
+    let user = args.single::<UserId>().unwrap_or(msg.author.id);
+
+    let response = {
+        let wallets = wallets(ctx).await;
+        let mut wallets = wallets.lock().await;
+        match wallets.get(guild) {
+            Ok(wallet) => {
+                let lines: Vec<String> = wallet.history(user.0).take(10).map(format_transaction).collect();
+                if lines.is_empty() {
+                    format!("{} has no transactions", user.mention())
+                } else {
+                    format!("Last transactions of {}:\n{}", user.mention(), lines.join("\n"))
+                }
+            }
+            Err(why) => {
+                println!("WALLET ERROR: {:?}", why);
+                String::from("The wallets couldn't be read")
+            }
+        }
+    };
+    msg.channel_id.say(ctx, response).await?;
+    Ok(())
+}
+
+// a!grant @user <amount>
+#[command]
+#[required_permissions("ADMINISTRATOR")]
+async fn grant(ctx: &Context, msg: &Message, args: Args) -> CommandResult {
+    admin_transaction(ctx, msg, args, true).await
+}
+
+// a!revoke @user <amount>
+#[command]
+#[required_permissions("ADMINISTRATOR")]
+async fn revoke(ctx: &Context, msg: &Message, args: Args) -> CommandResult {
+    admin_transaction(ctx, msg, args, false).await
+}
+
+async fn admin_transaction(ctx: &Context, msg: &Message, mut args: Args, grant: bool) -> CommandResult {
+    let guild = msg.guild_id.unwrap_or_default().0;
+    let (user, amount) = match (args.single::<UserId>(), args.single::<u64>()) {
+        (Ok(user), Ok(amount)) => (user, amount),
+        _ => {
+            let usage = if grant { "a!grant @user <amount>" } else { "a!revoke @user <amount>" };
+            msg.channel_id.say(ctx, format!("Usage: {}", usage)).await?;
+            return Ok(());
+        }
+    };
+
+    let by = msg.author.id.0;
+    let result = {
+        let wallets = wallets(ctx).await;
+        let mut wallets = wallets.lock().await;
+        wallets.transact(guild, |w| {
+            if grant { w.grant(user.0, amount, by) } else { w.revoke(user.0, amount, by) }
+        })
+    };
+    let response = match result {
+        Ok(t) => format!("{} now has {} chips ({})", user.mention(), t.balance, format_transaction(&t)),
+        Err(why) => format!("Couldn't change the wallet of {}: {}", user.mention(), why),
+    };
+    msg.channel_id.say(ctx, response).await?;
+    Ok(())
+}
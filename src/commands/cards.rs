@@ -0,0 +1,105 @@
+// Hole cards are sent to each player by DM at the start of every hand, a!cards
+// sends them again. Players with closed DMs are told in the table's channel
+// how to get their cards.
+use serenity::{
+    Error,
+    client::Context,
+    http::error::Error as HttpError,
+    model::{
+        channel::Message,
+        id::{ChannelId, UserId}
+    },
+    framework::standard::{
+        CommandResult,
+        macros::{
+            command, group
+        }
+    }
+};
+
+use super::{say, tables};
+
+// Discord's error code for users that don't accept DMs from the bot
+const CANNOT_MESSAGE_USER: isize = 50007;
+
+#[group]
+#[commands(cards)]
+struct Cards;
+
+enum Delivery {
+    Sent,
+    DmsClosed,
+    Failed,
+}
+
+async fn send_dm(ctx: &Context, user: u64, text: String) -> Delivery {
+    let result = match UserId(user).create_dm_channel(ctx).await {
+        Ok(dm) => dm.say(ctx, text).await.map(|_| ()),
+        Err(why) => Err(why),
+    };
+    match result {
+        Ok(()) => Delivery::Sent,
+        Err(Error::Http(why)) if matches!(
+            why.as_ref(),
+            HttpError::UnsuccessfulRequest(response) if response.error.code == CANNOT_MESSAGE_USER
+        ) => Delivery::DmsClosed,
+        Err(why) => {
+            println!("DM ERROR: to {}: {:?}", user, why);
+            Delivery::Failed
+        }
+    }
+}
+
+async fn warn(ctx: &Context, channel: u64, user: u64, delivery: Delivery) {
+    let warning = match delivery {
+        Delivery::Sent => return,
+        Delivery::DmsClosed => format!(
+            "<@{}> I can't send you your cards, your DMs are closed. Allow direct messages \
+             from server members in your privacy settings, then use `a!cards`.",
+            user
+        ),
+        Delivery::Failed => format!(
+            "<@{}> I couldn't send you your cards, use `a!cards` to try again.",
+            user
+        ),
+    };
+    say(ctx, ChannelId(channel), warning).await;
+}
+
+// Sends a player their cards, telling them in the table's channel when that
+// didn't work.
+pub async fn send_cards(ctx: &Context, channel: u64, user: u64, text: String) {
+    let delivery = send_dm(ctx, user, text).await;
+    warn(ctx, channel, user, delivery).await;
+}
+
+// Resends your cards, from the table's channel or from anywhere else
+#[command]
+async fn cards(ctx: &Context, msg: &Message) -> CommandResult {
+    let user = msg.author.id.0;
+    let cards = {
+        let tables = tables(ctx).await;
+        let tables = tables.lock().await;
+        match tables.seated_at(user).and_then(|channel| tables.get(channel)) {
+            Some(table) => table.hole_cards(user).map(|text| (table.channel, text)).ok_or("You have no cards in this hand"),
+            None => Err("You are not seated at any table"),
+        }
+    };
+    let (channel, text) = match cards {
+        Ok(cards) => cards,
+        Err(why) => {
+            say(ctx, msg.channel_id, why).await;
+            return Ok(());
+        }
+    };
+    match send_dm(ctx, user, text).await {
+        Delivery::Sent if msg.is_private() => (),
+        Delivery::Sent => {
+            if let Err(why) = msg.react(ctx, '✅').await {
+                println!("REACT ERROR: {:?}", why);
+            }
+        }
+        delivery => warn(ctx, channel, user, delivery).await,
+    }
+    Ok(())
+}
@@ -0,0 +1,257 @@
+// Cash games: a!cash opens a table that plays on for as long as players come
+// and go. Players sit down with a!join for any buy-in in the table's range,
+// a!topup their stack or rebuy once bust, a!sitout for a few hands and a!leave
+// with their stack, right away or once the hand they are in is over. Chips
+// come from and go back to the players' wallets.
+use serenity::{
+    client::Context,
+    model::channel::Message,
+    prelude::Mentionable,
+    framework::standard::{
+        Args, CommandResult,
+        macros::{
+            command, group
+        }
+    }
+};
+
+use discord_texas_holdem::table::{Settings, Table, Tables};
+use discord_texas_holdem::wallet::Wallets;
+
+use super::{play::deal_next, save, say, tables, wallets, Outbox};
+
+#[group]
+#[only_in(guilds)]
+#[commands(cash, topup, sitout)]
+struct Cash;
+
+// a!cash [chips] [buyin=200-1000] and the other settings of a!create, the host
+// buying in for the chips given or the largest buy-in
+#[command]
+#[aliases("ring")]
+async fn cash(ctx: &Context, msg: &Message, mut args: Args) -> CommandResult {
+    let chips = args.single::<u32>().ok();
+    let settings = match args.rest().parse::<Settings>() {
+        Ok(settings) => settings,
+        Err(why) => {
+            say(ctx, msg.channel_id, format!("Couldn't open the table: {}", why)).await;
+            return Ok(());
+        }
+    };
+    let guild = msg.guild_id.unwrap_or_default().0;
+    let channel = msg.channel_id.0;
+    let user = msg.author.id.0;
+    let chips = chips.unwrap_or(settings.buy_in);
+    let table = Table::open(guild, channel, user, settings).with_cash(chips);
+    if let Err(why) = table.check_buy_in(chips) {
+        say(ctx, msg.channel_id, format!("Couldn't open the table: {}", why)).await;
+        return Ok(());
+    }
+
+    let mut out = Outbox::new();
+    {
+        let tables = tables(ctx).await;
+        let mut tables = tables.lock().await;
+        let wallets = wallets(ctx).await;
+        let mut wallets = wallets.lock().await;
+        open_table(&mut out, &mut tables, &mut wallets, table, chips);
+    }
+    out.send(ctx).await;
+    Ok(())
+}
+
+fn open_table(out: &mut Outbox, tables: &mut Tables, wallets: &mut Wallets, table: Table, chips: u32) {
+    let (guild, channel, user) = (table.guild, table.channel, table.host);
+    if let Err(why) = tables.open(table) {
+        out.say(channel, format!("Couldn't open the table: {}", why));
+        return;
+    }
+    if let Err(why) = wallets.transact(guild, |w| w.buy_in(user, chips as u64, channel)) {
+        if let Err(why) = tables.remove(channel) {
+            println!("SAVE ERROR: table in {}: {:?}", channel, why);
+        }
+        out.say(channel, format!("Couldn't buy in: {}", why));
+        return;
+    }
+
+    save(tables, channel);
+    out.say(channel, tables.get(channel).map(|t| t.status()).unwrap_or_default());
+}
+
+// a!topup <chips>, also how busted players rebuy
+#[command]
+#[aliases("rebuy", "addon")]
+async fn topup(ctx: &Context, msg: &Message, mut args: Args) -> CommandResult {
+    let chips = match args.single::<u32>() {
+        Ok(chips) => chips,
+        Err(_) => {
+            say(ctx, msg.channel_id, "Usage: a!topup <chips>").await;
+            return Ok(());
+        }
+    };
+    let mut out = Outbox::new();
+    {
+        let tables = tables(ctx).await;
+        let mut tables = tables.lock().await;
+        let wallets = wallets(ctx).await;
+        let mut wallets = wallets.lock().await;
+        top_up(&mut out, &mut tables, &mut wallets, msg, chips);
+    }
+    out.send(ctx).await;
+    Ok(())
+}
+
+fn top_up(out: &mut Outbox, tables: &mut Tables, wallets: &mut Wallets, msg: &Message, chips: u32) {
+    let channel = msg.channel_id.0;
+    let user = msg.author.id.0;
+    let table = match tables.get_mut(channel) {
+        Some(table) => table,
+        None => {
+            out.say(channel, "There is no table in this channel");
+            return;
+        }
+    };
+    if let Err(why) = table.check_top_up(user, chips) {
+        out.say(channel, format!("You can't top up: {}", why));
+        return;
+    }
+    if let Err(why) = wallets.transact(table.guild, |w| w.buy_in(user, chips as u64, channel)) {
+        out.say(channel, format!("Couldn't buy in: {}", why));
+        return;
+    }
+    table.top_up(user, chips).expect("the top-up was checked");
+
+    out.say(channel, format!("{} adds {} chips to their stack.", msg.author.mention(), chips));
+    if table.can_deal() {
+        deal_next(out, tables, channel);
+    } else {
+        out.say(channel, table.status());
+        save(tables, channel);
+    }
+}
+
+#[command]
+#[aliases("away")]
+async fn sitout(ctx: &Context, msg: &Message) -> CommandResult {
+    let channel = msg.channel_id.0;
+    let user = msg.author.id.0;
+    let text = {
+        let tables = tables(ctx).await;
+        let mut tables = tables.lock().await;
+        match tables.get_mut(channel) {
+            Some(table) if table.is_seated(user) && table.game.is_some() => {
+                table.sit_out(user);
+                save(&mut tables, channel);
+                format!("{} is sitting out, use `a!back` to play again.", msg.author.mention())
+            }
+            Some(table) if table.is_seated(user) => String::from("The game hasn't started yet"),
+            Some(_) => String::from("You are not seated at this table"),
+            None => String::from("There is no table in this channel"),
+        }
+    };
+    say(ctx, msg.channel_id, text).await;
+    Ok(())
+}
+
+// a!join [chips] at a cash table, buying in for the chips given or the largest
+// buy-in. Hands are dealt as soon as two players are in.
+pub fn sit_down(out: &mut Outbox, tables: &mut Tables, wallets: &mut Wallets, msg: &Message, chips: Option<u32>) {
+    let channel = msg.channel_id.0;
+    let user = msg.author.id.0;
+    let chips = chips
+        .or_else(|| tables.get(channel).map(|t| t.settings.buy_in))
+        .unwrap_or_default();
+    let table = match tables.sit_down(channel, user, chips) {
+        Ok(table) => table,
+        Err(why) => {
+            out.say(channel, format!("You can't join: {}", why));
+            return;
+        }
+    };
+
+    if let Err(why) = wallets.transact(table.guild, |w| w.buy_in(user, chips as u64, channel)) {
+        table.stand_up(user).expect("the player was just seated");
+        out.say(channel, format!("Couldn't buy in: {}", why));
+        return;
+    }
+
+    if table.can_deal() {
+        deal_next(out, tables, channel);
+    } else {
+        out.say(channel, table.status());
+        save(tables, channel);
+    }
+}
+
+// a!leave at a cash table: players not in the hand being played take their
+// stack right away, the others once it is over.
+pub fn stand_up(out: &mut Outbox, tables: &mut Tables, wallets: &mut Wallets, msg: &Message) {
+    let channel = msg.channel_id.0;
+    let user = msg.author.id.0;
+    let table = match tables.get_mut(channel) {
+        Some(table) => table,
+        None => return,
+    };
+    match table.stand_up(user) {
+        Ok(Some(stack)) => {
+            cash_out(wallets, table.guild, channel, &[(user, stack)]);
+            out.say(channel, format!("{} leaves the table with {} chips.", msg.author.mention(), stack));
+            close_or_save(out, tables, channel);
+        }
+        Ok(None) => {
+            save(tables, channel);
+            out.say(channel, format!("{} leaves the table once this hand is over.", msg.author.mention()));
+        }
+        Err(why) => out.say(channel, format!("You can't leave: {}", why)),
+    }
+}
+
+// Once a hand at a cash table is over: pays out the players leaving and deals
+// the next hand if there are still two players to deal to.
+pub fn after_hand(out: &mut Outbox, tables: &mut Tables, wallets: &mut Wallets, channel: u64) {
+    let table = match tables.get_mut(channel) {
+        Some(table) => table,
+        None => return,
+    };
+    let left = table.settle();
+    cash_out(wallets, table.guild, channel, &left);
+    if !left.is_empty() {
+        let lines: Vec<String> = left
+            .iter()
+            .map(|(user, stack)| format!("<@{}> leaves the table with {} chips.", user, stack))
+            .collect();
+        out.say(channel, lines.join("\n"));
+    }
+    if table.seats.is_empty() {
+        close_or_save(out, tables, channel);
+    } else if table.can_deal() {
+        deal_next(out, tables, channel);
+    } else {
+        out.say(channel, table.status());
+        save(tables, channel);
+    }
+}
+
+fn cash_out(wallets: &mut Wallets, guild: u64, channel: u64, stacks: &[(u64, u32)]) {
+    for &(user, chips) in stacks {
+        if chips == 0 {
+            continue;
+        }
+        if let Err(why) = wallets.transact(guild, |w| w.cash_out(user, chips as u64, channel)) {
+            println!("WALLET ERROR: cash out of {} in {}: {:?}", user, channel, why);
+        }
+    }
+}
+
+// Closes a cash table everyone left, saves it otherwise.
+fn close_or_save(out: &mut Outbox, tables: &mut Tables, channel: u64) {
+    let empty = tables.get(channel).map(|t| t.seats.is_empty()).unwrap_or(false);
+    if !empty {
+        save(tables, channel);
+        return;
+    }
+    if let Err(why) = tables.remove(channel) {
+        println!("SAVE ERROR: table in {}: {:?}", channel, why);
+    }
+    out.say(channel, "Everyone left, the table is closed");
+}
@@ -0,0 +1,309 @@
+// Lobby: a!create opens a table in the channel or thread, or a!tournament a
+// sit-and-go, players a!join and a!leave it, and the host a!start's the game
+// once enough players are seated. Joining buys in with chips from the player's
+// wallet, leaving before the game starts returns them. Cash tables opened with
+// a!cash are joined and left the same way, while they play.
+use serenity::{
+    client::Context,
+    model::channel::Message,
+    framework::standard::{
+        Args, CommandResult,
+        macros::{
+            command, group
+        }
+    }
+};
+
+use discord_texas_holdem::table::{self, Settings, Table, TableError, Tables, Tournament};
+use discord_texas_holdem::wallet::Wallets;
+
+use super::{cash, directors, save, say, tables, tournament, wallets, Outbox};
+
+// How long tables can go without anything happening before they are closed
+const LOBBY_IDLE: u64 = 30 * 60;
+const GAME_IDLE: u64 = 2 * 60 * 60;
+
+#[group]
+#[only_in(guilds)]
+#[commands(create, tournament, join, leave, seats, start, list_tables)]
+struct Lobby;
+
+// a!create [structure=no-limit|pot-limit|fixed-limit] [cap=4] [buyin=1000] [blinds=5/10]
+//          [ante=0] [bbante=0] [seats=2-9] [clock=30] [timebank=60]
+#[command]
+#[aliases("poker", "play_poker")]
+async fn create(ctx: &Context, msg: &Message, args: Args) -> CommandResult {
+    match args.rest().parse::<Settings>().and_then(Settings::fixed_buy_in) {
+        Ok(settings) => open(ctx, msg, settings, None).await,
+        Err(why) => say(ctx, msg.channel_id, format!("Couldn't open the table: {}", why)).await,
+    }
+    Ok(())
+}
+
+// a!tournament [buyin=1000] [stack=1500] [levels=10/20,15/30,25/50/5...] [every=10m|20h]
+//              [payouts=65/35] and the other settings of a!create but the blinds
+#[command]
+#[aliases("sng", "sitandgo")]
+async fn tournament(ctx: &Context, msg: &Message, args: Args) -> CommandResult {
+    match Tournament::parse(args.rest()) {
+        Ok((settings, tournament)) => open(ctx, msg, settings, Some(tournament)).await,
+        Err(why) => say(ctx, msg.channel_id, format!("Couldn't open the tournament: {}", why)).await,
+    }
+    Ok(())
+}
+
+// Opens a table with the author as host, bought in.
+async fn open(ctx: &Context, msg: &Message, settings: Settings, tournament: Option<Tournament>) {
+    let mut out = Outbox::new();
+    {
+        let tables = tables(ctx).await;
+        let mut tables = tables.lock().await;
+        let wallets = wallets(ctx).await;
+        let mut wallets = wallets.lock().await;
+        open_table(&mut out, &mut tables, &mut wallets, msg, settings, tournament);
+    }
+    out.send(ctx).await;
+}
+
+fn open_table(
+    out: &mut Outbox,
+    tables: &mut Tables,
+    wallets: &mut Wallets,
+    msg: &Message,
+    settings: Settings,
+    tournament: Option<Tournament>,
+) {
+    let guild = msg.guild_id.unwrap_or_default().0;
+    let channel = msg.channel_id.0;
+    let user = msg.author.id.0;
+    let mut table = Table::open(guild, channel, user, settings);
+    if let Some(tournament) = tournament {
+        table = table.with_tournament(tournament);
+    }
+    if let Err(why) = tables.open(table) {
+        out.say(channel, format!("Couldn't open the table: {}", why));
+        return;
+    }
+
+    if let Err(why) = wallets.transact(guild, |w| w.buy_in(user, settings.buy_in as u64, channel)) {
+        if let Err(why) = tables.remove(channel) {
+            println!("SAVE ERROR: table in {}: {:?}", channel, why);
+        }
+        out.say(channel, format!("Couldn't buy in: {}", why));
+        return;
+    }
+
+    save(tables, channel);
+    out.say(channel, tables.get(channel).map(|t| t.status()).unwrap_or_default());
+}
+
+// a!join, or a!join [chips] at a cash table
+#[command]
+#[aliases("sit")]
+async fn join(ctx: &Context, msg: &Message, mut args: Args) -> CommandResult {
+    let chips = args.single::<u32>().ok();
+    let mut out = Outbox::new();
+    {
+        let tables = tables(ctx).await;
+        let mut tables = tables.lock().await;
+        let wallets = wallets(ctx).await;
+        let mut wallets = wallets.lock().await;
+        if tables.get(msg.channel_id.0).map(|t| t.cash.is_some()).unwrap_or(false) {
+            cash::sit_down(&mut out, &mut tables, &mut wallets, msg, chips);
+        } else {
+            join_table(&mut out, &mut tables, &mut wallets, msg);
+        }
+    }
+    out.send(ctx).await;
+    Ok(())
+}
+
+fn join_table(out: &mut Outbox, tables: &mut Tables, wallets: &mut Wallets, msg: &Message) {
+    let guild = msg.guild_id.unwrap_or_default().0;
+    let channel = msg.channel_id.0;
+    let user = msg.author.id.0;
+    let table = match tables.join(channel, user) {
+        Ok(table) => table,
+        Err(TableError::NoTable) => {
+            out.say(channel, "There is no table in this channel, open one with `a!create`");
+            return;
+        }
+        Err(why) => {
+            out.say(channel, format!("You can't join: {}", why));
+            return;
+        }
+    };
+
+    let buy_in = table.settings.buy_in as u64;
+    if let Err(why) = wallets.transact(guild, |w| w.buy_in(user, buy_in, channel)) {
+        table.leave(user).expect("the player was just seated");
+        out.say(channel, format!("Couldn't buy in: {}", why));
+        return;
+    }
+
+    out.say(channel, table.status());
+    save(tables, channel);
+}
+
+#[command]
+async fn leave(ctx: &Context, msg: &Message) -> CommandResult {
+    let mut out = Outbox::new();
+    {
+        let tables = tables(ctx).await;
+        let mut tables = tables.lock().await;
+        let wallets = wallets(ctx).await;
+        let mut wallets = wallets.lock().await;
+        leave_table(&mut out, &mut tables, &mut wallets, msg);
+    }
+    out.send(ctx).await;
+    Ok(())
+}
+
+fn leave_table(out: &mut Outbox, tables: &mut Tables, wallets: &mut Wallets, msg: &Message) {
+    let guild = msg.guild_id.unwrap_or_default().0;
+    let channel = msg.channel_id.0;
+    let user = msg.author.id.0;
+    let table = match tables.get_mut(channel) {
+        Some(table) => table,
+        None => {
+            out.say(channel, "There is no table in this channel");
+            return;
+        }
+    };
+    if table.cash.is_some() {
+        cash::stand_up(out, tables, wallets, msg);
+        return;
+    }
+    if let Err(why) = table.leave(user) {
+        out.say(channel, format!("You can't leave: {}", why));
+        return;
+    }
+
+    let buy_in = table.settings.buy_in as u64;
+    if let Err(why) = wallets.transact(guild, |w| w.cash_out(user, buy_in, channel)) {
+        println!("WALLET ERROR: refund of {} in {}: {:?}", user, channel, why);
+    }
+
+    if table.seats.is_empty() {
+        if let Err(why) = tables.remove(channel) {
+            println!("SAVE ERROR: table in {}: {:?}", channel, why);
+        }
+        out.say(channel, "Everyone left, the table is closed");
+    } else {
+        out.say(channel, table.status());
+        save(tables, channel);
+    }
+}
+
+#[command]
+#[aliases("table")]
+async fn seats(ctx: &Context, msg: &Message) -> CommandResult {
+    let status = {
+        let tables = tables(ctx).await;
+        let tables = tables.lock().await;
+        tables.get(msg.channel_id.0).map(|t| t.status())
+    };
+    match status {
+        Some(status) => say(ctx, msg.channel_id, status).await,
+        None => say(ctx, msg.channel_id, "There is no table in this channel").await,
+    }
+    Ok(())
+}
+
+#[command]
+async fn start(ctx: &Context, msg: &Message) -> CommandResult {
+    let channel = msg.channel_id.0;
+    let mut out = Outbox::new();
+    {
+        let tables = tables(ctx).await;
+        let mut tables = tables.lock().await;
+        match tables.get_mut(channel) {
+            Some(table) => match table.start(msg.author.id.0) {
+                Ok(_) => {
+                    if let Some(commitment) = table.commitment() {
+                        out.say(channel, commitment);
+                    }
+                    out.say(channel, table.status());
+                    out.hole_cards(table);
+                    save(&mut tables, channel);
+                }
+                Err(why) => out.say(channel, format!("The game can't start: {}", why)),
+            },
+            None => out.say(channel, "There is no table in this channel"),
+        }
+    }
+    out.send(ctx).await;
+    Ok(())
+}
+
+// Every table of the server
+#[command]
+#[aliases("tables")]
+async fn list_tables(ctx: &Context, msg: &Message) -> CommandResult {
+    let guild = msg.guild_id.unwrap_or_default().0;
+    let lines: Vec<String> = {
+        let tables = tables(ctx).await;
+        let tables = tables.lock().await;
+        tables
+            .in_guild(guild)
+            .iter()
+            .map(|t| {
+                let state = if t.game.is_some() { "playing" } else { "open" };
+                if t.cash.is_some() {
+                    return format!("<#{}>: cash game, {} seated, {}", t.channel, t.seats.len(), t.settings);
+                }
+                match &t.tournament {
+                    Some(tournament) => format!("<#{}>: {}, {} seated, {}, {}", t.channel, state, t.seats.len(), tournament, t.settings),
+                    None => format!("<#{}>: {}, {} seated, {}", t.channel, state, t.seats.len(), t.settings),
+                }
+            })
+            .collect()
+    };
+    if lines.is_empty() {
+        say(ctx, msg.channel_id, "There are no tables, open one with `a!create`").await;
+    } else {
+        say(ctx, msg.channel_id, lines.join("\n")).await;
+    }
+    Ok(())
+}
+
+// Closes the tables nobody played at for a while, giving everyone seated their
+// chips back, or their buy-in for tournaments, and the tournaments nobody
+// launched or plays at anymore.
+pub async fn close_abandoned(ctx: &Context) {
+    let mut out = Outbox::new();
+    {
+        let tables = tables(ctx).await;
+        let mut tables = tables.lock().await;
+        let directors = directors(ctx).await;
+        let mut directors = directors.lock().await;
+        let wallets = wallets(ctx).await;
+        let mut wallets = wallets.lock().await;
+        let now = table::now();
+        tournament::close_abandoned(&mut out, &mut tables, &mut directors, &mut wallets, now, LOBBY_IDLE, GAME_IDLE);
+        close_tables(&mut out, &mut tables, &mut wallets, now);
+    }
+    out.send(ctx).await;
+}
+
+fn close_tables(out: &mut Outbox, tables: &mut Tables, wallets: &mut Wallets, now: u64) {
+    for channel in tables.abandoned(now, LOBBY_IDLE, GAME_IDLE) {
+        let table = match tables.remove(channel) {
+            Ok(Some(table)) => table,
+            Ok(None) => continue,
+            Err(why) => {
+                println!("SAVE ERROR: table in {}: {:?}", channel, why);
+                continue;
+            }
+        };
+        for (user, chips) in table.cash_outs() {
+            if chips == 0 {
+                continue;
+            }
+            if let Err(why) = wallets.transact(table.guild, |w| w.cash_out(user, chips, channel)) {
+                println!("WALLET ERROR: refund of {} in {}: {:?}", user, channel, why);
+            }
+        }
+        out.say(channel, "Nothing happened here for a while, the table is closed and everyone got their chips back");
+    }
+}
@@ -0,0 +1,115 @@
+// Commands of the bot, with the tables, tournaments and wallets they share
+// through the client's TypeMap.
+use std::sync::Arc;
+
+use serenity::{
+    client::Context,
+    model::id::ChannelId,
+    prelude::{
+        Mutex, TypeMapKey
+    }
+};
+
+use discord_texas_holdem::director::Directors;
+use discord_texas_holdem::table::{Table, Tables};
+use discord_texas_holdem::wallet::Wallets;
+
+pub mod bank;
+pub mod cards;
+pub mod cash;
+pub mod lobby;
+pub mod play;
+pub mod tournament;
+
+pub struct TableRegistry;
+
+impl TypeMapKey for TableRegistry {
+    type Value = Arc<Mutex<Tables>>;
+}
+
+pub struct DirectorRegistry;
+
+impl TypeMapKey for DirectorRegistry {
+    type Value = Arc<Mutex<Directors>>;
+}
+
+pub struct WalletRegistry;
+
+impl TypeMapKey for WalletRegistry {
+    type Value = Arc<Mutex<Wallets>>;
+}
+
+// Tables have to be locked before tournaments, and both before wallets, when
+// more than one is needed.
+pub async fn tables(ctx: &Context) -> Arc<Mutex<Tables>> {
+    let data = ctx.data.read().await;
+    data.get::<TableRegistry>().expect("Tables not initialized").clone()
+}
+
+pub async fn directors(ctx: &Context) -> Arc<Mutex<Directors>> {
+    let data = ctx.data.read().await;
+    data.get::<DirectorRegistry>().expect("Tournaments not initialized").clone()
+}
+
+pub async fn wallets(ctx: &Context) -> Arc<Mutex<Wallets>> {
+    let data = ctx.data.read().await;
+    data.get::<WalletRegistry>().expect("Wallets not initialized").clone()
+}
+
+pub async fn say(ctx: &Context, channel: ChannelId, text: impl std::fmt::Display) {
+    if let Err(why) = channel.say(ctx, text).await {
+        println!("MESSAGE ERROR: {:?}", why);
+    }
+}
+
+// Messages and hole cards queued while the tables are locked, sent once the
+// locks are released so a slow Discord doesn't hold up every other table.
+#[derive(Default)]
+pub struct Outbox {
+    messages: Vec<Outgoing>,
+}
+
+enum Outgoing {
+    Say { channel: u64, text: String },
+    HoleCards { channel: u64, user: u64, text: String },
+}
+
+impl Outbox {
+    pub fn new() -> Self {
+        Outbox::default()
+    }
+
+    pub fn say(&mut self, channel: u64, text: impl std::fmt::Display) {
+        self.messages.push(Outgoing::Say { channel, text: text.to_string() });
+    }
+
+    // Every player dealt in the current hand gets their cards by DM.
+    pub fn hole_cards(&mut self, table: &Table) {
+        let game = match &table.game {
+            Some(game) => game,
+            None => return,
+        };
+        for player in game.players.iter() {
+            if let Some(text) = table.hole_cards(player.id) {
+                self.messages.push(Outgoing::HoleCards { channel: table.channel, user: player.id, text });
+            }
+        }
+    }
+
+    pub async fn send(self, ctx: &Context) {
+        for message in self.messages {
+            match message {
+                Outgoing::Say { channel, text } => say(ctx, ChannelId(channel), text).await,
+                Outgoing::HoleCards { channel, user, text } => cards::send_cards(ctx, channel, user, text).await,
+            }
+        }
+    }
+}
+
+// Saves a table after a change, a failure is only logged since the table keeps
+// running in memory.
+pub fn save(tables: &mut Tables, channel: u64) {
+    if let Err(why) = tables.save(channel) {
+        println!("SAVE ERROR: table in {}: {:?}", channel, why);
+    }
+}
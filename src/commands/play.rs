@@ -0,0 +1,346 @@
+// Actions during a hand: a!fold, a!check, a!call, a!bet 200, a!raise to 600
+// and a!allin. Only the player whose turn it is can act, anything the hand
+// doesn't allow is turned down with the reason. The clock acts for players
+// who take too long, and players sat out for it or with a!sitout come back
+// with a!back. Players mix their own entropy into the next hand's shuffle with
+// a!entropy, on top of the ids of their action messages.
+use serenity::{
+    client::Context,
+    model::channel::Message,
+    prelude::Mentionable,
+    framework::standard::{
+        Args, CommandResult,
+        macros::{
+            command, group
+        }
+    }
+};
+
+use discord_texas_holdem::director::Directors;
+use discord_texas_holdem::poker::{fair, Action};
+use discord_texas_holdem::table::{self, ClockEvent, Tables};
+use discord_texas_holdem::wallet::Wallets;
+
+use super::{cash, directors, save, say, tables, tournament, wallets, Outbox};
+
+#[group]
+#[only_in(guilds)]
+#[commands(fold, check, call, bet, raise, allin, back, entropy)]
+struct Play;
+
+#[command]
+async fn fold(ctx: &Context, msg: &Message) -> CommandResult {
+    play(ctx, msg, Action::Fold).await
+}
+
+#[command]
+async fn check(ctx: &Context, msg: &Message) -> CommandResult {
+    play(ctx, msg, Action::Check).await
+}
+
+#[command]
+async fn call(ctx: &Context, msg: &Message) -> CommandResult {
+    play(ctx, msg, Action::Call).await
+}
+
+// a!bet <amount>
+#[command]
+async fn bet(ctx: &Context, msg: &Message, mut args: Args) -> CommandResult {
+    match args.single::<u32>() {
+        Ok(amount) => play(ctx, msg, Action::Bet(amount)).await,
+        Err(_) => {
+            say(ctx, msg.channel_id, "Usage: a!bet <amount>").await;
+            Ok(())
+        }
+    }
+}
+
+// a!raise [to] <amount>, raising the bet to the amount
+#[command]
+async fn raise(ctx: &Context, msg: &Message, mut args: Args) -> CommandResult {
+    if args.current() == Some("to") {
+        args.advance();
+    }
+    match args.single::<u32>() {
+        Ok(to) => play(ctx, msg, Action::Raise(to)).await,
+        Err(_) => {
+            say(ctx, msg.channel_id, "Usage: a!raise to <amount>").await;
+            Ok(())
+        }
+    }
+}
+
+#[command]
+#[aliases("all-in", "shove")]
+async fn allin(ctx: &Context, msg: &Message) -> CommandResult {
+    play(ctx, msg, Action::AllIn).await
+}
+
+#[command]
+#[aliases("sitin")]
+async fn back(ctx: &Context, msg: &Message) -> CommandResult {
+    let channel = msg.channel_id.0;
+    let user = msg.author.id.0;
+    let mut out = Outbox::new();
+    {
+        let tables = tables(ctx).await;
+        let mut tables = tables.lock().await;
+        match tables.get_mut(channel) {
+            Some(table) if table.is_sitting_out(user) => {
+                table.sit_in(user);
+                let deal = table.cash.is_some() && table.can_deal();
+                out.say(channel, format!("Welcome back {}!", msg.author.mention()));
+                if deal {
+                    deal_next(&mut out, &mut tables, channel);
+                } else {
+                    save(&mut tables, channel);
+                }
+            }
+            Some(_) => out.say(channel, "You are not sitting out"),
+            None => out.say(channel, "There is no table in this channel"),
+        }
+    }
+    out.send(ctx).await;
+    Ok(())
+}
+
+// a!entropy <anything>, mixed into the shuffle of the next hand, or of the
+// first hands of a tournament that hasn't been launched yet
+#[command]
+async fn entropy(ctx: &Context, msg: &Message, args: Args) -> CommandResult {
+    let channel = msg.channel_id.0;
+    let added = {
+        let tables = tables(ctx).await;
+        let mut tables = tables.lock().await;
+        match tables.get_mut(channel) {
+            Some(table) => {
+                let added = table.add_entropy(args.rest());
+                if added {
+                    save(&mut tables, channel);
+                }
+                Some(added)
+            }
+            None => {
+                let directors = directors(ctx).await;
+                let mut directors = directors.lock().await;
+                let added = directors.get_mut(channel).map(|d| d.add_entropy(args.rest()));
+                if added == Some(true) {
+                    if let Err(why) = directors.save(channel) {
+                        println!("SAVE ERROR: tournament in {}: {:?}", channel, why);
+                    }
+                }
+                added
+            }
+        }
+    };
+    match added {
+        Some(true) => {
+            if let Err(why) = msg.react(ctx, '✅').await {
+                println!("REACT ERROR: {:?}", why);
+            }
+        }
+        Some(false) => {
+            let text = format!(
+                "Nothing was added: give some text, the next hand takes up to {} pieces of entropy and tournaments only take it before they start.",
+                fair::MAX_ENTROPY,
+            );
+            say(ctx, msg.channel_id, text).await;
+        }
+        None => say(ctx, msg.channel_id, "There is no table in this channel").await,
+    }
+    Ok(())
+}
+
+async fn play(ctx: &Context, msg: &Message, action: Action) -> CommandResult {
+    let mut out = Outbox::new();
+    let acted = {
+        let tables = tables(ctx).await;
+        let mut tables = tables.lock().await;
+        let directors = directors(ctx).await;
+        let mut directors = directors.lock().await;
+        let wallets = wallets(ctx).await;
+        let mut wallets = wallets.lock().await;
+        act(&mut out, &mut tables, &mut directors, &mut wallets, msg, action)
+    };
+    if acted {
+        if let Err(why) = msg.react(ctx, '✅').await {
+            println!("REACT ERROR: {:?}", why);
+        }
+    }
+    out.send(ctx).await;
+    Ok(())
+}
+
+fn act(
+    out: &mut Outbox,
+    tables: &mut Tables,
+    directors: &mut Directors,
+    wallets: &mut Wallets,
+    msg: &Message,
+    action: Action,
+) -> bool {
+    let channel = msg.channel_id.0;
+    let table = match tables.get_mut(channel) {
+        Some(table) => table,
+        None => {
+            out.say(channel, "There is no table in this channel");
+            return false;
+        }
+    };
+    if let Err(why) = table.act(msg.author.id.0, action, table::now()) {
+        out.say(channel, format!("{} you can't do that: {}", msg.author.mention(), why));
+        return false;
+    }
+    // The ids of the messages players act with are entropy for the next hand.
+    table.add_entropy(&msg.id.0.to_string());
+    after_action(out, tables, directors, wallets, channel);
+    true
+}
+
+// Moves the table on after someone acted: shows whose turn it is, or the
+// results of a finished hand followed by the next hand, or the winner of the
+// game once only one player has chips left. Tournaments also eliminate busted
+// players and raise the blinds between hands, multi-table ones leave that to
+// their director. Cash tables play on for as long as there are players.
+pub fn after_action(
+    out: &mut Outbox,
+    tables: &mut Tables,
+    directors: &mut Directors,
+    wallets: &mut Wallets,
+    channel: u64,
+) {
+    let table = match tables.get_mut(channel) {
+        Some(table) => table,
+        None => return,
+    };
+    let over = table.game.as_ref().map(|g| g.over).unwrap_or(false);
+    if !over {
+        let status = table.status();
+        save(tables, channel);
+        out.say(channel, status);
+        return;
+    }
+
+    if let Some(results) = table.results() {
+        out.say(channel, results);
+    }
+    if let Some(director) = table.director {
+        tournament::after_hand(out, tables, directors, wallets, director, channel);
+        return;
+    }
+    if table.cash.is_some() {
+        cash::after_hand(out, tables, wallets, channel);
+        return;
+    }
+    let busted: Vec<String> = table
+        .eliminate()
+        .into_iter()
+        .map(|(user, place)| format!("<@{}> is out in {} place.", user, table::ordinal(place)))
+        .collect();
+    if !busted.is_empty() {
+        out.say(channel, busted.join("\n"));
+    }
+    if let Some(winner) = table.winner() {
+        let guild = table.guild;
+        let cash_outs = table.cash_outs();
+        for &(user, chips) in &cash_outs {
+            if chips == 0 {
+                continue;
+            }
+            if let Err(why) = wallets.transact(guild, |w| w.cash_out(user, chips, channel)) {
+                println!("WALLET ERROR: cash out of {} in {}: {:?}", user, channel, why);
+            }
+        }
+        let text = if table.tournament.is_some() {
+            let prizes: Vec<String> = cash_outs
+                .iter()
+                .enumerate()
+                .filter(|(_, &(_, chips))| chips > 0)
+                .map(|(i, (user, chips))| format!("{}: <@{}> wins {}", table::ordinal(i + 1), user, chips))
+                .collect();
+            format!("<@{}> wins the tournament, prizes are paid to their wallets!\n{}", winner, prizes.join("\n"))
+        } else {
+            format!("<@{}> wins the game, their chips are back in their wallet!", winner)
+        };
+        if let Err(why) = tables.remove(channel) {
+            println!("SAVE ERROR: table in {}: {:?}", channel, why);
+        }
+        out.say(channel, text);
+        return;
+    }
+    deal_next(out, tables, channel);
+}
+
+// Deals the next hand at a table between hands, after raising the blinds when
+// the schedule of its tournament says so.
+pub fn deal_next(out: &mut Outbox, tables: &mut Tables, channel: u64) {
+    let table = match tables.get_mut(channel) {
+        Some(table) => table,
+        None => return,
+    };
+    if let Some(level) = table.level_up(table::now()) {
+        let blinds = table.settings.blinds;
+        let mut text = format!("Blinds are up! Level {}: {}/{}", level, blinds.small, blinds.big);
+        if blinds.ante > 0 {
+            text.push_str(&format!(", ante {}", blinds.ante));
+        }
+        out.say(channel, text);
+    }
+    if let Err(why) = table.deal() {
+        println!("DEAL ERROR: table in {}: {:?}", channel, why);
+        return;
+    }
+    if let Some(commitment) = table.commitment() {
+        out.say(channel, commitment);
+    }
+    out.say(channel, table.status());
+    out.hole_cards(table);
+    save(tables, channel);
+}
+
+// Runs the clock of every table, called every second. What the clocks did is
+// only announced once the locks are released.
+pub async fn run_clocks(ctx: &Context) {
+    let mut out = Outbox::new();
+    {
+        let tables = tables(ctx).await;
+        let mut tables = tables.lock().await;
+        let directors = directors(ctx).await;
+        let mut directors = directors.lock().await;
+        let wallets = wallets(ctx).await;
+        let mut wallets = wallets.lock().await;
+        let now = table::now();
+        let channels: Vec<u64> = tables.iter().map(|t| t.channel).collect();
+        for channel in channels {
+            let event = match tables.get_mut(channel).and_then(|t| t.tick(now)) {
+                Some(event) => event,
+                None => continue,
+            };
+            match event {
+                // The clock was marked as warned, saved so a restart doesn't warn again
+                ClockEvent::Warning { player, left } => {
+                    save(&mut tables, channel);
+                    out.say(channel, format!("<@{}> {} seconds left to act!", player, left));
+                    continue;
+                }
+                ClockEvent::TimedOut { player, action, sat_out } => {
+                    let mut text = format!("<@{}> ran out of time and {}.", player, action_name(action));
+                    if sat_out {
+                        text.push_str(" You are now sitting out, use `a!back` to play again.");
+                    }
+                    out.say(channel, text);
+                }
+                ClockEvent::SittingOut { .. } => (),
+            }
+            after_action(&mut out, &mut tables, &mut directors, &mut wallets, channel);
+        }
+    }
+    out.send(ctx).await;
+}
+
+fn action_name(action: Action) -> &'static str {
+    match action {
+        Action::Check => "checks",
+        _ => "folds",
+    }
+}
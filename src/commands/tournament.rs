@@ -0,0 +1,403 @@
+// Multi-table tournaments: a!mtt opens one in the channel with the channels or
+// threads its tables run in, players a!register and a!unregister, and the host
+// a!launch'es it. Registering buys in with chips from the player's wallet.
+// Once running, the tables play like any other, the director moving players
+// between them after each hand.
+use serenity::{
+    client::Context,
+    model::{
+        channel::Message,
+        id::ChannelId
+    },
+    framework::standard::{
+        Args, CommandResult,
+        macros::{
+            command, group
+        }
+    }
+};
+
+use discord_texas_holdem::director::{Director, DirectorError, DirectorEvent, Directors};
+use discord_texas_holdem::table::{self, Tables, Tournament};
+use discord_texas_holdem::wallet::Wallets;
+
+use super::{directors, play::deal_next, save, say, tables, wallets, Outbox};
+
+#[group]
+#[only_in(guilds)]
+#[commands(mtt, register, unregister, launch, standings)]
+struct Tournaments;
+
+// a!mtt <#channel>... [buyin=1000] [stack=1500] [levels=...] [every=10m] [payouts=65/35]
+//       [seats=2-9] and the other settings of a!tournament, seats being per table
+#[command]
+#[aliases("multitable")]
+async fn mtt(ctx: &Context, msg: &Message, mut args: Args) -> CommandResult {
+    let mut channels = Vec::new();
+    while let Ok(channel) = args.single::<ChannelId>() {
+        channels.push(channel.0);
+    }
+    let (settings, tournament) = match Tournament::parse(args.rest()) {
+        Ok(parsed) => parsed,
+        Err(why) => {
+            say(ctx, msg.channel_id, format!("Couldn't open the tournament: {}", why)).await;
+            return Ok(());
+        }
+    };
+    let guild = msg.guild_id.unwrap_or_default().0;
+    let channel = msg.channel_id.0;
+    let director = match Director::open(guild, channel, msg.author.id.0, settings, tournament, channels) {
+        Ok(director) => director,
+        Err(why) => {
+            say(ctx, msg.channel_id, format!("Couldn't open the tournament: {} (usage: a!mtt <#channel>... [settings])", why)).await;
+            return Ok(());
+        }
+    };
+
+    let mut out = Outbox::new();
+    {
+        let tables = tables(ctx).await;
+        let tables = tables.lock().await;
+        let directors = directors(ctx).await;
+        let mut directors = directors.lock().await;
+        open(&mut out, &tables, &mut directors, director);
+    }
+    out.send(ctx).await;
+    Ok(())
+}
+
+fn open(out: &mut Outbox, tables: &Tables, directors: &mut Directors, director: Director) {
+    let channel = director.channel;
+    if let Some(&taken) = director.channels.iter().find(|&&c| tables.get(c).is_some()) {
+        out.say(channel, format!("Couldn't open the tournament: {}", DirectorError::ChannelTaken { channel: taken }));
+        return;
+    }
+    let status = match directors.open(director) {
+        Ok(director) => director.status(),
+        Err(why) => {
+            out.say(channel, format!("Couldn't open the tournament: {}", why));
+            return;
+        }
+    };
+    save_director(directors, channel);
+    out.say(channel, status);
+}
+
+#[command]
+#[aliases("enter")]
+async fn register(ctx: &Context, msg: &Message) -> CommandResult {
+    let mut out = Outbox::new();
+    {
+        let tables = tables(ctx).await;
+        let tables = tables.lock().await;
+        let directors = directors(ctx).await;
+        let mut directors = directors.lock().await;
+        let wallets = wallets(ctx).await;
+        let mut wallets = wallets.lock().await;
+        register_player(&mut out, &tables, &mut directors, &mut wallets, msg.channel_id.0, msg.author.id.0);
+    }
+    out.send(ctx).await;
+    Ok(())
+}
+
+fn register_player(out: &mut Outbox, tables: &Tables, directors: &mut Directors, wallets: &mut Wallets, channel: u64, user: u64) {
+    if let Some(seated) = tables.seated_at(user) {
+        out.say(channel, format!("You can't register: you are already seated at the table in <#{}>", seated));
+        return;
+    }
+    match directors.registered_in(user) {
+        Some(other) if other != channel => {
+            out.say(channel, format!("You can't register: you are already in the tournament in <#{}>", other));
+            return;
+        }
+        _ => (),
+    }
+    let director = match directors.get_mut(channel) {
+        Some(director) => director,
+        None => {
+            out.say(channel, "There is no tournament in this channel, open one with `a!mtt`");
+            return;
+        }
+    };
+    if let Err(why) = director.register(user) {
+        out.say(channel, format!("You can't register: {}", why));
+        return;
+    }
+
+    let buy_in = director.settings.buy_in as u64;
+    if let Err(why) = wallets.transact(director.guild, |w| w.buy_in(user, buy_in, channel)) {
+        director.unregister(user).expect("the player was just registered");
+        out.say(channel, format!("Couldn't buy in: {}", why));
+        return;
+    }
+
+    let status = director.status();
+    save_director(directors, channel);
+    out.say(channel, status);
+}
+
+#[command]
+async fn unregister(ctx: &Context, msg: &Message) -> CommandResult {
+    let mut out = Outbox::new();
+    {
+        let directors = directors(ctx).await;
+        let mut directors = directors.lock().await;
+        let wallets = wallets(ctx).await;
+        let mut wallets = wallets.lock().await;
+        unregister_player(&mut out, &mut directors, &mut wallets, msg.channel_id.0, msg.author.id.0);
+    }
+    out.send(ctx).await;
+    Ok(())
+}
+
+fn unregister_player(out: &mut Outbox, directors: &mut Directors, wallets: &mut Wallets, channel: u64, user: u64) {
+    let director = match directors.get_mut(channel) {
+        Some(director) => director,
+        None => {
+            out.say(channel, "There is no tournament in this channel");
+            return;
+        }
+    };
+    if let Err(why) = director.unregister(user) {
+        out.say(channel, format!("You can't unregister: {}", why));
+        return;
+    }
+
+    let buy_in = director.settings.buy_in as u64;
+    if let Err(why) = wallets.transact(director.guild, |w| w.cash_out(user, buy_in, channel)) {
+        director.register(user).expect("the player was just unregistered");
+        out.say(channel, format!("Couldn't refund your buy-in, you are still registered: {}", why));
+        return;
+    }
+
+    let status = director.status();
+    save_director(directors, channel);
+    out.say(channel, status);
+}
+
+// Seats everyone and deals the first hand at every table.
+#[command]
+#[aliases("start_mtt")]
+async fn launch(ctx: &Context, msg: &Message) -> CommandResult {
+    let channel = msg.channel_id.0;
+    let mut out = Outbox::new();
+    {
+        let tables = tables(ctx).await;
+        let mut tables = tables.lock().await;
+        let directors = directors(ctx).await;
+        let mut directors = directors.lock().await;
+        start(&mut out, &mut tables, &mut directors, msg.author.id.0, channel);
+    }
+    out.send(ctx).await;
+    Ok(())
+}
+
+fn start(out: &mut Outbox, tables: &mut Tables, directors: &mut Directors, user: u64, channel: u64) {
+    let director = match directors.get_mut(channel) {
+        Some(director) => director,
+        None => {
+            out.say(channel, "There is no tournament in this channel");
+            return;
+        }
+    };
+    if let Err(why) = director.start(user, tables) {
+        out.say(channel, format!("The tournament can't start: {}", why));
+        return;
+    }
+
+    let (status, channels) = (director.status(), director.channels.clone());
+    save_director(directors, channel);
+    out.say(channel, status);
+    for table_channel in channels {
+        save(tables, table_channel);
+        let table = match tables.get(table_channel) {
+            Some(table) => table,
+            None => continue,
+        };
+        if let Some(commitment) = table.commitment() {
+            out.say(table_channel, commitment);
+        }
+        out.say(table_channel, table.status());
+        out.hole_cards(table);
+    }
+}
+
+#[command]
+#[aliases("mtt_status")]
+async fn standings(ctx: &Context, msg: &Message) -> CommandResult {
+    let status = {
+        let directors = directors(ctx).await;
+        let directors = directors.lock().await;
+        directors.get(msg.channel_id.0).map(|d| d.status())
+    };
+    match status {
+        Some(status) => say(ctx, msg.channel_id, status).await,
+        None => say(ctx, msg.channel_id, "There is no tournament in this channel").await,
+    }
+    Ok(())
+}
+
+// Hands a finished hand at one of a tournament's tables to its director:
+// announces who busted and who moved, breaks tables, deals on where there are
+// players to deal to, and pays the prizes once a single player is left.
+pub fn after_hand(
+    out: &mut Outbox,
+    tables: &mut Tables,
+    directors: &mut Directors,
+    wallets: &mut Wallets,
+    id: u64,
+    channel: u64,
+) {
+    let director = match directors.get_mut(id) {
+        Some(director) => director,
+        None => {
+            println!("DIRECTOR ERROR: table in {} has no tournament in {}", channel, id);
+            return;
+        }
+    };
+    let mut deal = vec![channel];
+    for event in director.after_hand(tables, channel) {
+        match event {
+            DirectorEvent::Eliminated { player, place } => {
+                out.say(channel, format!("<@{}> is out in {} place.", player, table::ordinal(place)));
+            }
+            DirectorEvent::Moved { player, from, to } => {
+                out.say(from, format!("<@{}> moves to <#{}> to balance the tables.", player, to));
+                out.say(to, format!("<@{}> joins the table from <#{}>.", player, from));
+                deal.push(to);
+            }
+            DirectorEvent::Broken { channel: broken } => {
+                if let Err(why) = tables.remove(broken) {
+                    println!("SAVE ERROR: table in {}: {:?}", broken, why);
+                }
+                out.say(broken, "This table is broken, everyone moved to the other tables.");
+            }
+            DirectorEvent::FinalTable { channel: last } => {
+                out.say(id, format!("**Final table** in <#{}>!", last));
+                out.say(last, "**Final table!**");
+            }
+            DirectorEvent::Finished { winner } => {
+                finish(out, tables, directors, wallets, id, winner);
+                return;
+            }
+        }
+    }
+    save_director(directors, id);
+
+    for channel in deal {
+        let table = match tables.get(channel) {
+            Some(table) => table,
+            None => continue,
+        };
+        let between_hands = table.game.as_ref().map(|g| g.over).unwrap_or(false);
+        if !between_hands {
+            save(tables, channel);
+        } else if table.seats.len() >= 2 {
+            deal_next(out, tables, channel);
+        } else {
+            save(tables, channel);
+            out.say(channel, "Waiting for players to be moved to this table.");
+        }
+    }
+}
+
+// Pays the prizes and closes the tournament with its last table.
+fn finish(out: &mut Outbox, tables: &mut Tables, directors: &mut Directors, wallets: &mut Wallets, id: u64, winner: u64) {
+    let director = match directors.remove(id) {
+        Ok(Some(director)) => director,
+        Ok(None) => return,
+        Err(why) => {
+            println!("SAVE ERROR: tournament in {}: {:?}", id, why);
+            return;
+        }
+    };
+    let cash_outs = director.cash_outs();
+    for &(user, chips) in &cash_outs {
+        if let Err(why) = wallets.transact(director.guild, |w| w.cash_out(user, chips, id)) {
+            println!("WALLET ERROR: prize of {} in {}: {:?}", user, id, why);
+        }
+    }
+    for &channel in &director.channels {
+        if let Err(why) = tables.remove(channel) {
+            println!("SAVE ERROR: table in {}: {:?}", channel, why);
+        }
+    }
+
+    let prizes: Vec<String> = cash_outs
+        .iter()
+        .enumerate()
+        .map(|(i, (user, chips))| format!("{}: <@{}> wins {}", table::ordinal(i + 1), user, chips))
+        .collect();
+    let text = format!("<@{}> wins the tournament, prizes are paid to their wallets!\n{}", winner, prizes.join("\n"));
+    for &channel in &director.channels {
+        if channel != id {
+            out.say(channel, &text);
+        }
+    }
+    out.say(id, text);
+}
+
+// Tournaments nobody launched for a while are closed, everyone registered
+// getting their buy-in back. Running ones nobody played at for a while are
+// closed too, paying the prizes by the standings so far.
+pub fn close_abandoned(
+    out: &mut Outbox,
+    tables: &mut Tables,
+    directors: &mut Directors,
+    wallets: &mut Wallets,
+    now: u64,
+    lobby_idle: u64,
+    game_idle: u64,
+) {
+    for channel in directors.abandoned(tables, now, lobby_idle, game_idle) {
+        let director = match directors.remove(channel) {
+            Ok(Some(director)) => director,
+            Ok(None) => continue,
+            Err(why) => {
+                println!("SAVE ERROR: tournament in {}: {:?}", channel, why);
+                continue;
+            }
+        };
+        let cash_outs = director.closing_cash_outs(tables);
+        let paid = if director.started { "prize" } else { "refund" };
+        for &(user, chips) in &cash_outs {
+            if chips == 0 {
+                continue;
+            }
+            if let Err(why) = wallets.transact(director.guild, |w| w.cash_out(user, chips, channel)) {
+                println!("WALLET ERROR: {} of {} in {}: {:?}", paid, user, channel, why);
+            }
+        }
+        if !director.started {
+            out.say(channel, "Nobody launched the tournament for a while, it is closed and everyone got their buy-in back");
+            continue;
+        }
+
+        for &table_channel in &director.channels {
+            if let Err(why) = tables.remove(table_channel) {
+                println!("SAVE ERROR: table in {}: {:?}", table_channel, why);
+            }
+        }
+        let prizes: Vec<String> = cash_outs
+            .iter()
+            .enumerate()
+            .map(|(i, (user, chips))| format!("{}: <@{}> wins {}", table::ordinal(i + 1), user, chips))
+            .collect();
+        let text = format!(
+            "Nothing happened at the tournament's tables for a while, it is closed and the prizes are paid by the standings so far!\n{}",
+            prizes.join("\n")
+        );
+        for &table_channel in &director.channels {
+            if table_channel != channel {
+                out.say(table_channel, &text);
+            }
+        }
+        out.say(channel, text);
+    }
+}
+
+fn save_director(directors: &mut Directors, channel: u64) {
+    if let Err(why) = directors.save(channel) {
+        println!("SAVE ERROR: tournament in {}: {:?}", channel, why);
+    }
+}
@@ -0,0 +1,526 @@
+// Multi-table tournaments: a director registers players, seats them across the
+// channels or threads the tournament was given, moves players to keep the
+// tables balanced as others bust, and breaks tables down to a final table.
+// Every table is a regular `Table` the director moves players in and out of
+// between its hands.
+use std::cmp::Reverse;
+use std::collections::HashMap;
+use std::fmt;
+use std::io;
+
+use rand::seq::SliceRandom;
+use serde::{Deserialize, Serialize};
+
+use crate::poker::fair::{self, Shuffle};
+use crate::store::Store;
+use crate::table::{Restored, Settings, Table, Tables, Tournament, MIN_SEATS};
+
+#[cfg(test)]
+mod test;
+
+#[derive(PartialEq, Eq, Clone, Copy, Debug)]
+pub enum DirectorError {
+    ChannelTaken { channel: u64 },
+    NoChannels,
+    AlreadyRegistered,
+    NotRegistered,
+    Full { max: usize },
+    AlreadyStarted,
+    NotHost,
+    NotEnoughPlayers { registered: usize, min: usize },
+    SeatedElsewhere { player: u64, channel: u64 },
+}
+
+impl fmt::Display for DirectorError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            DirectorError::ChannelTaken { channel } =>
+                write!(f, "<#{}> already has a table or a tournament", channel),
+            DirectorError::NoChannels =>
+                write!(f, "the tournament needs at least one channel for its tables"),
+            DirectorError::AlreadyRegistered =>
+                write!(f, "you are already registered"),
+            DirectorError::NotRegistered =>
+                write!(f, "you are not registered"),
+            DirectorError::Full { max } =>
+                write!(f, "the tournament is full, its tables seat {} players", max),
+            DirectorError::AlreadyStarted =>
+                write!(f, "the tournament has already started"),
+            DirectorError::NotHost =>
+                write!(f, "only the host can do this"),
+            DirectorError::NotEnoughPlayers { registered, min } =>
+                write!(f, "{} players are not enough for a tournament, need at least {}", registered, min),
+            DirectorError::SeatedElsewhere { player, channel } =>
+                write!(f, "<@{}> is still seated at the table in <#{}>", player, channel),
+        }
+    }
+}
+
+impl std::error::Error for DirectorError {}
+
+// What happened after a hand, for the bot to announce
+#[derive(PartialEq, Eq, Clone, Copy, Debug)]
+pub enum DirectorEvent {
+    Eliminated { player: u64, place: usize },
+    Moved { player: u64, from: u64, to: u64 },
+    Broken { channel: u64 },
+    FinalTable { channel: u64 },
+    Finished { winner: u64 },
+}
+
+#[derive(PartialEq, Eq, Clone, Copy, Debug, Serialize, Deserialize)]
+pub struct Seat {
+    #[serde(with = "crate::poker::ids")]
+    pub player: u64,
+    #[serde(with = "crate::poker::ids")]
+    pub channel: u64,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Director {
+    #[serde(with = "crate::poker::ids")]
+    pub guild: u64,
+    // Channel the tournament was opened and is announced in
+    #[serde(with = "crate::poker::ids")]
+    pub channel: u64,
+    #[serde(with = "crate::poker::ids")]
+    pub host: u64,
+    // Settings of every table, `max_seats` being how many a table seats
+    pub settings: Settings,
+    pub tournament: Tournament,
+    // Players registered, then every entrant once it started
+    #[serde(with = "crate::poker::ids::list")]
+    pub players: Vec<u64>,
+    // Channels the tables can run in, then the ones still running
+    #[serde(with = "crate::poker::ids::list")]
+    pub channels: Vec<u64>,
+    pub seats: Vec<Seat>,
+    // Shuffles of the first hand at each table, committed to when the
+    // tournament opens so players can add their entropy before it starts
+    #[serde(default)]
+    pub shuffles: HashMap<u64, Shuffle>,
+    pub started: bool,
+    // When the tournament last changed, in seconds since the unix epoch
+    pub active: u64,
+}
+
+impl Director {
+    pub fn open(
+        guild: u64,
+        channel: u64,
+        host: u64,
+        settings: Settings,
+        tournament: Tournament,
+        channels: Vec<u64>,
+    ) -> Result<Self, DirectorError> {
+        if channels.is_empty() {
+            return Err(DirectorError::NoChannels);
+        }
+        Ok(Director {
+            guild,
+            channel,
+            host,
+            settings,
+            tournament,
+            players: Vec::new(),
+            shuffles: channels
+                .iter()
+                .map(|&c| (c, Shuffle::new(vec![c.to_string(), String::from("1")])))
+                .collect(),
+            channels,
+            seats: Vec::new(),
+            started: false,
+            active: crate::table::now(),
+        })
+    }
+
+    // Players every channel's table seats together
+    pub fn capacity(&self) -> usize {
+        self.channels.len() * self.settings.max_seats
+    }
+
+    pub fn is_registered(&self, user: u64) -> bool {
+        self.players.contains(&user)
+    }
+
+    pub fn register(&mut self, user: u64) -> Result<(), DirectorError> {
+        if self.started {
+            return Err(DirectorError::AlreadyStarted);
+        }
+        if self.is_registered(user) {
+            return Err(DirectorError::AlreadyRegistered);
+        }
+        if self.players.len() >= self.capacity() {
+            return Err(DirectorError::Full { max: self.capacity() });
+        }
+        self.players.push(user);
+        Ok(())
+    }
+
+    pub fn unregister(&mut self, user: u64) -> Result<(), DirectorError> {
+        if self.started {
+            return Err(DirectorError::AlreadyStarted);
+        }
+        if !self.is_registered(user) {
+            return Err(DirectorError::NotRegistered);
+        }
+        self.players.retain(|&id| id != user);
+        Ok(())
+    }
+
+    // Channel of the table a player is seated at
+    pub fn seat_of(&self, player: u64) -> Option<u64> {
+        self.seats.iter().find(|s| s.player == player).map(|s| s.channel)
+    }
+
+    fn players_at(&self, channel: u64) -> usize {
+        self.seats.iter().filter(|s| s.channel == channel).count()
+    }
+
+    // Draws the players' seats at random over as few tables as they fit in,
+    // opening and dealing each of them.
+    pub fn start(&mut self, user: u64, tables: &mut Tables) -> Result<(), DirectorError> {
+        if self.started {
+            return Err(DirectorError::AlreadyStarted);
+        }
+        if user != self.host {
+            return Err(DirectorError::NotHost);
+        }
+        let min = self.settings.min_seats.max(MIN_SEATS);
+        if self.players.len() < min {
+            return Err(DirectorError::NotEnoughPlayers { registered: self.players.len(), min });
+        }
+        if let Some(&channel) = self.channels.iter().find(|&&c| tables.get(c).is_some()) {
+            return Err(DirectorError::ChannelTaken { channel });
+        }
+        for &player in &self.players {
+            if let Some(channel) = tables.seated_at(player) {
+                return Err(DirectorError::SeatedElsewhere { player, channel });
+            }
+        }
+
+        let count = self.players.len().div_ceil(self.settings.max_seats);
+        self.channels.truncate(count);
+        let mut players = self.players.clone();
+        players.shuffle(&mut rand::thread_rng());
+        self.seats = players
+            .iter()
+            .enumerate()
+            .map(|(i, &player)| Seat { player, channel: self.channels[i % count] })
+            .collect();
+
+        self.tournament.started = crate::table::now();
+        let settings = Settings { min_seats: MIN_SEATS, ..self.settings };
+        for &channel in &self.channels {
+            let seated: Vec<u64> = self.seats.iter().filter(|s| s.channel == channel).map(|s| s.player).collect();
+            let mut table = Table::open(self.guild, channel, seated[0], settings)
+                .with_tournament(self.tournament.clone());
+            table.director = Some(self.channel);
+            if let Some(shuffle) = self.shuffles.remove(&channel) {
+                table.next_shuffle = Some(shuffle);
+            }
+            for &player in &seated[1..] {
+                table.join(player).expect("the table seats every player drawn to it");
+            }
+            table.start(seated[0]).expect("the table has enough players");
+            tables.open(table).expect("the channel and players are free");
+        }
+        self.started = true;
+        Ok(())
+    }
+
+    // Takes the hand a table just finished into account: eliminates the
+    // players it busted, then breaks the table if the players left fit at one
+    // table fewer, or moves players from it to the smallest table until they
+    // seat at most one player more. The table deals on once it has players,
+    // whoever got moved joins their new table's next hand.
+    pub fn after_hand(&mut self, tables: &mut Tables, channel: u64) -> Vec<DirectorEvent> {
+        let mut events = Vec::new();
+        let table = match tables.get_mut(channel) {
+            Some(table) if table.game.as_ref().map(|g| g.over).unwrap_or(false) => table,
+            _ => return events,
+        };
+
+        for player in table.busted() {
+            if table.unseat(player).is_err() {
+                continue;
+            }
+            self.seats.retain(|s| s.player != player);
+            self.tournament.eliminated.push(player);
+            let place = self.players.len() + 1 - self.tournament.eliminated.len();
+            events.push(DirectorEvent::Eliminated { player, place });
+        }
+        if let [seat] = self.seats.as_slice() {
+            events.push(DirectorEvent::Finished { winner: seat.player });
+            return events;
+        }
+
+        let needed = self.seats.len().div_ceil(self.settings.max_seats);
+        if self.channels.len() > needed {
+            let players: Vec<u64> = self.seats.iter().filter(|s| s.channel == channel).map(|s| s.player).collect();
+            self.channels.retain(|&c| c != channel);
+            for player in players {
+                self.move_player(tables, player, channel, &mut events);
+            }
+            events.push(DirectorEvent::Broken { channel });
+            if let [last] = self.channels.as_slice() {
+                events.push(DirectorEvent::FinalTable { channel: *last });
+            }
+            return events;
+        }
+
+        while let Some(smallest) = self.smallest(channel) {
+            if self.players_at(channel) <= self.players_at(smallest) + 1 {
+                break;
+            }
+            let player = match tables.get(channel).and_then(next_big_blind) {
+                Some(player) => player,
+                None => break,
+            };
+            self.move_player(tables, player, channel, &mut events);
+        }
+        events
+    }
+
+    // Table other than the given one seating the fewest players
+    fn smallest(&self, except: u64) -> Option<u64> {
+        self.channels
+            .iter()
+            .copied()
+            .filter(|&c| c != except)
+            .min_by_key(|&c| self.players_at(c))
+    }
+
+    fn move_player(&mut self, tables: &mut Tables, player: u64, from: u64, events: &mut Vec<DirectorEvent>) {
+        let to = match self.smallest(from) {
+            Some(to) => to,
+            None => return,
+        };
+        let stack = match tables.get_mut(from).map(|t| t.unseat(player)) {
+            Some(Ok(stack)) => stack,
+            _ => return,
+        };
+        if let Some(table) = tables.get_mut(to) {
+            table.seat(player, stack).expect("the player was just unseated");
+        }
+        for seat in self.seats.iter_mut().filter(|s| s.player == player) {
+            seat.channel = to;
+        }
+        events.push(DirectorEvent::Moved { player, from, to });
+    }
+
+    // Entrants by the place they finished in once the tournament is over
+    pub fn standings(&self) -> Option<Vec<u64>> {
+        match self.seats.as_slice() {
+            [winner] if self.started => Some(
+                std::iter::once(winner.player)
+                    .chain(self.tournament.eliminated.iter().rev().copied())
+                    .collect(),
+            ),
+            _ => None,
+        }
+    }
+
+    // Entrants by the place they would finish in if the tournament stopped
+    // now: the players still in by their stacks, then the ones out.
+    pub fn standings_so_far(&self, tables: &Tables) -> Vec<u64> {
+        let mut left: Vec<(u64, u32)> = self
+            .seats
+            .iter()
+            .map(|seat| {
+                let stack = tables
+                    .get(seat.channel)
+                    .and_then(|t| t.stacks().into_iter().find(|&(id, _)| id == seat.player))
+                    .map(|(_, stack)| stack)
+                    .unwrap_or(0);
+                (seat.player, stack)
+            })
+            .collect();
+        left.sort_by_key(|&(_, stack)| Reverse(stack));
+        left.into_iter()
+            .map(|(player, _)| player)
+            .chain(self.tournament.eliminated.iter().rev().copied())
+            .collect()
+    }
+
+    pub fn prize_pool(&self) -> u64 {
+        self.players.len() as u64 * self.settings.buy_in as u64
+    }
+
+    // Chips each player gets in their wallet if the tournament closed now:
+    // the prizes once it's over, everyone's buy-in back otherwise.
+    pub fn cash_outs(&self) -> Vec<(u64, u64)> {
+        match self.standings() {
+            Some(standings) => {
+                let prizes = self.tournament.prizes(standings.len(), self.prize_pool());
+                standings.into_iter().zip(prizes).collect()
+            }
+            None => self.players.iter().map(|&id| (id, self.settings.buy_in as u64)).collect(),
+        }
+    }
+
+    // Chips each player gets in their wallet when the tournament is closed
+    // before it's over: everyone's buy-in back before it started, the prizes
+    // of the standings so far once it did.
+    pub fn closing_cash_outs(&self, tables: &Tables) -> Vec<(u64, u64)> {
+        if !self.started || self.standings().is_some() {
+            return self.cash_outs();
+        }
+        let standings = self.standings_so_far(tables);
+        let prizes = self.tournament.prizes(standings.len(), self.prize_pool());
+        standings.into_iter().zip(prizes).collect()
+    }
+
+    // Mixes a player's input into the first hand of every table, until it starts.
+    pub fn add_entropy(&mut self, input: &str) -> bool {
+        if self.started {
+            return false;
+        }
+        let mut added = false;
+        for shuffle in self.shuffles.values_mut() {
+            added |= shuffle.add_entropy(input);
+        }
+        added
+    }
+
+    // Summary of the tournament, written as a Discord message.
+    pub fn status(&self) -> String {
+        let mut lines = vec![format!("**Multi-table tournament**: {}, {}", self.tournament, self.settings)];
+        if !self.started {
+            let players: Vec<String> = self.players.iter().map(|id| format!("<@{}>", id)).collect();
+            lines.push(format!("Registered ({}/{}): {}", self.players.len(), self.capacity(), players.join(", ")));
+            let channels: Vec<String> = self.channels.iter().map(|c| format!("<#{}>", c)).collect();
+            lines.push(format!("Tables in {}", channels.join(", ")));
+            let commitments: Vec<String> = self
+                .channels
+                .iter()
+                .filter_map(|c| self.shuffles.get(c).map(|s| format!("<#{}> `{}`", c, fair::to_hex(&s.commitment()))))
+                .collect();
+            if !commitments.is_empty() {
+                lines.push(format!(
+                    "First hands are dealt from server seeds committed to as {}, mix in your own entropy with `a!entropy <anything>`.",
+                    commitments.join(", ")
+                ));
+            }
+            lines.push(format!(
+                "Register with `a!register`, <@{}> starts the tournament with `a!launch`.",
+                self.host
+            ));
+            return lines.join("\n");
+        }
+        lines.push(format!(
+            "{} of {} players left, prize pool {}",
+            self.seats.len(),
+            self.players.len(),
+            self.prize_pool(),
+        ));
+        for &channel in &self.channels {
+            lines.push(format!("<#{}>: {} players", channel, self.players_at(channel)));
+        }
+        lines.join("\n")
+    }
+}
+
+// Player to move off a table: the one who would post the big blind next hand
+fn next_big_blind(table: &Table) -> Option<u64> {
+    let game = table.game.as_ref()?;
+    game.big_blind_player
+        .and_then(|bb| game.players.next_after(bb, |p| p.money > 0))
+        .or_else(|| game.players.iter().find(|p| p.money > 0))
+        .map(|p| p.id)
+}
+
+// Every tournament by the channel it was opened in, saved to the store on
+// every change.
+pub struct Directors {
+    store: Store,
+    directors: HashMap<u64, Director>,
+}
+
+impl Directors {
+    pub fn new(store: Store) -> Self {
+        Directors {
+            store,
+            directors: HashMap::new(),
+        }
+    }
+
+    // Loads the stored tournaments that aren't running yet.
+    pub fn restore(&mut self) -> io::Result<Restored> {
+        let mut restored = Restored::default();
+        for (channel, director) in self.store.load_all::<Director>()? {
+            if self.directors.contains_key(&channel) {
+                continue;
+            }
+            match director {
+                Ok(director) => {
+                    self.directors.insert(channel, director);
+                    restored.tables.push(channel);
+                }
+                Err(why) => restored.failed.push((channel, why)),
+            }
+        }
+        Ok(restored)
+    }
+
+    pub fn get(&self, channel: u64) -> Option<&Director> {
+        self.directors.get(&channel)
+    }
+
+    // Changes to the tournament have to be followed by `save`.
+    pub fn get_mut(&mut self, channel: u64) -> Option<&mut Director> {
+        self.directors.get_mut(&channel)
+    }
+
+    // Channel of the tournament the user registered for or is still playing
+    pub fn registered_in(&self, user: u64) -> Option<u64> {
+        self.directors
+            .values()
+            .find(|d| if d.started { d.seat_of(user).is_some() } else { d.is_registered(user) })
+            .map(|d| d.channel)
+    }
+
+    pub fn open(&mut self, director: Director) -> Result<&mut Director, DirectorError> {
+        let taken = self.directors.values().flat_map(|d| std::iter::once(&d.channel).chain(&d.channels));
+        for &channel in taken {
+            if channel == director.channel || director.channels.contains(&channel) {
+                return Err(DirectorError::ChannelTaken { channel });
+            }
+        }
+        Ok(self.directors.entry(director.channel).or_insert(director))
+    }
+
+    pub fn save(&mut self, channel: u64) -> io::Result<()> {
+        match self.directors.get_mut(&channel) {
+            Some(director) => {
+                director.active = crate::table::now();
+                self.store.save(channel, director)
+            }
+            None => Ok(()),
+        }
+    }
+
+    pub fn remove(&mut self, channel: u64) -> io::Result<Option<Director>> {
+        self.store.remove(channel)?;
+        Ok(self.directors.remove(&channel))
+    }
+
+    // Tournaments nobody started for longer than `lobby_idle` seconds, and
+    // running ones nothing happened at for `game_idle`, at any of their tables
+    pub fn abandoned(&self, tables: &Tables, now: u64, lobby_idle: u64, game_idle: u64) -> Vec<u64> {
+        let mut channels: Vec<u64> = self
+            .directors
+            .values()
+            .filter(|d| {
+                if !d.started {
+                    return now.saturating_sub(d.active) > lobby_idle;
+                }
+                let active = d.channels.iter().filter_map(|&c| tables.get(c)).map(|t| t.active).fold(d.active, u64::max);
+                now.saturating_sub(active) > game_idle
+            })
+            .map(|d| d.channel)
+            .collect();
+        channels.sort_unstable();
+        channels
+    }
+}
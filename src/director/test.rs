@@ -0,0 +1,213 @@
+use std::fs;
+use std::process;
+
+use super::*;
+use crate::poker::Action;
+
+fn tables(name: &str) -> Tables {
+    let dir = std::env::temp_dir().join(format!("poker-directors-{}-{}", name, process::id()));
+    let _ = fs::remove_dir_all(&dir);
+    Tables::new(Store::open(dir).unwrap())
+}
+
+// A tournament opened in channel 1 by player 1, with tables of 3 in channels
+// 10, 11 and 12.
+fn director(players: u64) -> Director {
+    let settings = Settings { max_seats: 3, ..Settings::default() };
+    let mut director = Director::open(1, 1, 1, settings, Tournament::default(), vec![10, 11, 12]).unwrap();
+    for player in 1..=players {
+        director.register(player).unwrap();
+    }
+    director
+}
+
+fn seated(director: &Director, channel: u64) -> Vec<u64> {
+    let mut players: Vec<u64> = director.seats.iter().filter(|s| s.channel == channel).map(|s| s.player).collect();
+    players.sort_unstable();
+    players
+}
+
+fn finish_hand(tables: &mut Tables, channel: u64) {
+    let game = tables.get_mut(channel).unwrap().game.as_mut().unwrap();
+    while !game.over {
+        let id = game.current_player().unwrap().id;
+        game.act(id, Action::Fold).unwrap();
+    }
+}
+
+fn bust(tables: &mut Tables, channel: u64, players: &[u64]) {
+    let game = tables.get_mut(channel).unwrap().game.as_mut().unwrap();
+    for &id in players {
+        game.players.get_player_mut(id).unwrap().money = 0;
+    }
+}
+
+#[test]
+fn registration() {
+    let mut director = director(8);
+    assert_eq!(director.capacity(), 9);
+    assert_eq!(director.register(8), Err(DirectorError::AlreadyRegistered));
+    director.register(9).unwrap();
+    assert_eq!(director.register(10), Err(DirectorError::Full { max: 9 }));
+    director.unregister(9).unwrap();
+    assert_eq!(director.unregister(9), Err(DirectorError::NotRegistered));
+    assert!(director.status().contains("Registered (8/9)"));
+    assert_eq!(
+        Director::open(1, 1, 1, Settings::default(), Tournament::default(), vec![]).unwrap_err(),
+        DirectorError::NoChannels
+    );
+
+    let mut tables = tables("registration");
+    assert_eq!(director.start(2, &mut tables), Err(DirectorError::NotHost));
+    let mut lonely = self::director(1);
+    assert_eq!(
+        lonely.start(1, &mut tables),
+        Err(DirectorError::NotEnoughPlayers { registered: 1, min: 2 })
+    );
+    tables.open(Table::open(1, 12, 50, Settings::default())).unwrap();
+    assert_eq!(director.start(1, &mut tables), Err(DirectorError::ChannelTaken { channel: 12 }));
+    tables.join(12, 8).unwrap();
+    tables.remove(12).unwrap();
+    tables.open(Table::open(1, 20, 8, Settings::default())).unwrap();
+    assert_eq!(director.start(1, &mut tables), Err(DirectorError::SeatedElsewhere { player: 8, channel: 20 }));
+}
+
+#[test]
+fn seating() {
+    let mut tables = tables("seating");
+    let mut director = director(5);
+    assert!(director.add_entropy("early bird"));
+    let committed = director.shuffles[&10].clone();
+    director.start(1, &mut tables).unwrap();
+    assert_eq!(director.register(6), Err(DirectorError::AlreadyStarted));
+    assert!(!director.add_entropy("too late"));
+
+    // First hands are dealt from the seeds committed to at registration.
+    let dealt = tables.get(10).unwrap().shuffle.clone().unwrap();
+    assert_eq!(dealt.server_seed, committed.server_seed);
+    assert!(dealt.entropy.contains(&String::from("earlybird")));
+
+    // Five players fit at two tables, the third channel isn't needed.
+    assert_eq!(director.channels, vec![10, 11]);
+    assert_eq!(seated(&director, 10).len(), 3);
+    assert_eq!(seated(&director, 11).len(), 2);
+    for player in 1..=5 {
+        let channel = director.seat_of(player).unwrap();
+        assert_eq!(tables.seated_at(player), Some(channel));
+        let table = tables.get(channel).unwrap();
+        assert_eq!(table.director, Some(1));
+        assert!(table.stacks().contains(&(player, 1500)));
+    }
+    assert!(director.status().contains("5 of 5 players left, prize pool 5000"));
+}
+
+#[test]
+fn balancing_and_breaking() {
+    let mut tables = tables("balancing");
+    let mut director = director(6);
+    director.start(1, &mut tables).unwrap();
+    let (first, second) = (seated(&director, 10), seated(&director, 11));
+
+    // Two players bust at the first table, the one left waits for a player.
+    finish_hand(&mut tables, 10);
+    bust(&mut tables, 10, &first[..2]);
+    let events = director.after_hand(&mut tables, 10);
+    assert_eq!(events.len(), 2);
+    assert!(matches!(events[1], DirectorEvent::Eliminated { place: 5, .. }));
+    assert_eq!(director.seat_of(first[0]), None);
+    assert_eq!(tables.get(10).unwrap().seats, vec![first[2]]);
+
+    // The second table finishing its hand sends a player over.
+    finish_hand(&mut tables, 11);
+    let events = director.after_hand(&mut tables, 11);
+    let moved = match events.as_slice() {
+        [DirectorEvent::Moved { player, from: 11, to: 10 }] => *player,
+        other => panic!("unexpected {:?}", other),
+    };
+    assert_eq!(director.seat_of(moved), Some(10));
+    assert_eq!(tables.get(10).unwrap().seats.len(), 2);
+    assert!(tables.get_mut(10).unwrap().deal().is_ok());
+
+    // One more bust and the three players left fit at the final table.
+    let staying: Vec<u64> = second.iter().copied().filter(|&p| p != moved).collect();
+    finish_hand(&mut tables, 11);
+    bust(&mut tables, 11, &staying[..1]);
+    let events = director.after_hand(&mut tables, 11);
+    assert_eq!(events[0], DirectorEvent::Eliminated { player: staying[0], place: 4 });
+    assert_eq!(events[1], DirectorEvent::Moved { player: staying[1], from: 11, to: 10 });
+    assert_eq!(events[2], DirectorEvent::Broken { channel: 11 });
+    assert_eq!(events[3], DirectorEvent::FinalTable { channel: 10 });
+    assert_eq!(director.channels, vec![10]);
+    assert!(tables.get(11).unwrap().seats.is_empty());
+    let game = tables.get(10).unwrap().game.as_ref().unwrap();
+    assert!(game.players.get_player(staying[1]).unwrap().hand.is_none());
+
+    // The final table plays down to a winner.
+    finish_hand(&mut tables, 10);
+    let left: Vec<u64> = director.seats.iter().map(|s| s.player).collect();
+    tables.get_mut(10).unwrap().deal().unwrap();
+    finish_hand(&mut tables, 10);
+    bust(&mut tables, 10, &left[1..]);
+    let events = director.after_hand(&mut tables, 10);
+    assert_eq!(events.last(), Some(&DirectorEvent::Finished { winner: left[0] }));
+    let standings = director.standings().unwrap();
+    assert_eq!(standings.len(), 6);
+    assert_eq!(standings[0], left[0]);
+    assert_eq!(director.cash_outs(), vec![(standings[0], 3900), (standings[1], 2100)]);
+}
+
+#[test]
+fn abandoned_while_running() {
+    let dir = std::env::temp_dir().join(format!("poker-directors-abandoned-{}", process::id()));
+    let _ = fs::remove_dir_all(&dir);
+    let mut directors = Directors::new(Store::open(&dir).unwrap());
+    let mut tables = tables("abandoned");
+    let mut director = director(5);
+    director.start(1, &mut tables).unwrap();
+    director.active = 1000;
+    let (first, second) = (seated(&director, 10), seated(&director, 11));
+    directors.open(director).unwrap();
+    for channel in [10, 11] {
+        finish_hand(&mut tables, channel);
+        tables.get_mut(channel).unwrap().active = 1000;
+    }
+
+    // Running tournaments have the longer limit, kept alive by any table.
+    assert!(directors.abandoned(&tables, 1100, 60, 600).is_empty());
+    tables.get_mut(11).unwrap().active = 1500;
+    assert!(directors.abandoned(&tables, 1700, 60, 600).is_empty());
+    assert_eq!(directors.abandoned(&tables, 2200, 60, 600), vec![1]);
+
+    // Closing it pays the prizes by the stacks so far.
+    let stacks = [(10, first[0], 500), (10, first[1], 2500), (10, first[2], 100), (11, second[0], 1000), (11, second[1], 200)];
+    for (channel, player, money) in stacks {
+        let game = tables.get_mut(channel).unwrap().game.as_mut().unwrap();
+        game.players.get_player_mut(player).unwrap().money = money;
+    }
+    let director = directors.get(1).unwrap();
+    assert_eq!(director.standings_so_far(&tables), vec![first[1], second[0], first[0], second[1], first[2]]);
+    assert_eq!(director.closing_cash_outs(&tables), vec![(first[1], 3250), (second[0], 1750)]);
+}
+
+#[test]
+fn registry() {
+    let dir = std::env::temp_dir().join(format!("poker-directors-registry-{}", process::id()));
+    let _ = fs::remove_dir_all(&dir);
+    let mut directors = Directors::new(Store::open(&dir).unwrap());
+    directors.open(director(2)).unwrap();
+    let other = Director::open(1, 2, 2, Settings::default(), Tournament::default(), vec![11]).unwrap();
+    assert_eq!(directors.open(other).unwrap_err(), DirectorError::ChannelTaken { channel: 11 });
+    assert_eq!(directors.registered_in(2), Some(1));
+    assert_eq!(directors.registered_in(3), None);
+    directors.save(1).unwrap();
+    let tables = tables("registry-tables");
+    let now = crate::table::now();
+    assert_eq!(directors.abandoned(&tables, now, 60, 600), Vec::<u64>::new());
+    assert_eq!(directors.abandoned(&tables, now + 61, 60, 600), vec![1]);
+
+    let mut restarted = Directors::new(Store::open(&dir).unwrap());
+    assert_eq!(restarted.restore().unwrap().tables, vec![1]);
+    assert_eq!(restarted.get(1).unwrap().players, vec![1, 2]);
+    restarted.remove(1).unwrap();
+    assert!(Directors::new(Store::open(&dir).unwrap()).restore().unwrap().tables.is_empty());
+}
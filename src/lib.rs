@@ -0,0 +1,5 @@
+pub mod director;
+pub mod poker;
+pub mod store;
+pub mod table;
+pub mod wallet;
@@ -1,135 +1,195 @@
 use std::env;
 use std::fs;
-use std::time::Duration;
+use std::path::Path;
 use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Duration;
 
 use serenity::{
     async_trait,
     client::{
         Client, Context, EventHandler
     },
-    collector::CollectReply,
     model::{
-        user::User,
-        channel::{
-        Message, EmbedField
-        }
+        channel::Message,
+        gateway::Ready
     },
+    prelude::Mutex,
     framework::standard::{
-        Args, CommandOptions, CommandResult, CommandGroup, StandardFramework,
+        Args, CommandResult, DispatchError, StandardFramework,
         macros::{
-            command, group
+            command, group, hook
         }
-    },
-    utils::Colour
+    }
 };
 
-mod poker;
+use discord_texas_holdem::director::Directors;
+use discord_texas_holdem::poker::fair;
+use discord_texas_holdem::store::Store;
+use discord_texas_holdem::table::Tables;
+use discord_texas_holdem::wallet::Wallets;
+
+mod commands;
+
+use commands::{
+    DirectorRegistry, Outbox, TableRegistry, WalletRegistry,
+    bank::BANK_GROUP,
+    cards::CARDS_GROUP,
+    cash::CASH_GROUP,
+    lobby::LOBBY_GROUP,
+    play::PLAY_GROUP,
+    tournament::TOURNAMENTS_GROUP
+};
 
 #[group]
-#[commands(play_poker)]
+#[commands(verify)]
 struct General;
 
-struct Handler;
+// How often abandoned tables are looked for and turn clocks run
+const CLEANUP_INTERVAL: Duration = Duration::from_secs(60);
+const CLOCK_INTERVAL: Duration = Duration::from_secs(1);
+
+struct Handler {
+    tasks_started: AtomicBool,
+}
 
 #[async_trait]
-impl EventHandler for Handler {}
+impl EventHandler for Handler {
+    // Also called on reconnects, only tables that aren't running yet are restored
+    async fn ready(&self, ctx: Context, _: Ready) {
+        if !self.tasks_started.swap(true, Ordering::SeqCst) {
+            let cleanup_ctx = ctx.clone();
+            tokio::spawn(async move {
+                loop {
+                    tokio::time::sleep(CLEANUP_INTERVAL).await;
+                    commands::lobby::close_abandoned(&cleanup_ctx).await;
+                }
+            });
+            let clock_ctx = ctx.clone();
+            tokio::spawn(async move {
+                loop {
+                    tokio::time::sleep(CLOCK_INTERVAL).await;
+                    commands::play::run_clocks(&clock_ctx).await;
+                }
+            });
+        }
+
+        let mut out = Outbox::new();
+        {
+            let tables = commands::tables(&ctx).await;
+            let mut tables = tables.lock().await;
+            match tables.restore() {
+                Ok(restored) => {
+                    for (channel, why) in restored.failed {
+                        println!("RESTORE ERROR: table in {}: {:?}", channel, why);
+                    }
+                    for channel in restored.tables {
+                        if let Some(table) = tables.get(channel) {
+                            out.say(channel, format!("The bot restarted, resuming the table.\n{}", table.status()));
+                        }
+                    }
+                }
+                Err(why) => println!("RESTORE ERROR: {:?}", why),
+            }
+
+            let directors = commands::directors(&ctx).await;
+            let mut directors = directors.lock().await;
+            match directors.restore() {
+                Ok(restored) => {
+                    for (channel, why) in restored.failed {
+                        println!("RESTORE ERROR: tournament in {}: {:?}", channel, why);
+                    }
+                }
+                Err(why) => println!("RESTORE ERROR: {:?}", why),
+            }
+        }
+        out.send(&ctx).await;
+    }
+}
 
 #[tokio::main]
 async fn main() {
     let framework = StandardFramework::new()
         .configure(|c| c.prefix("a!")) // set the bot's prefix to "a!"
-        .group(&GENERAL_GROUP);
+        .on_dispatch_error(dispatch_error)
+        .group(&GENERAL_GROUP)
+        .group(&LOBBY_GROUP)
+        .group(&CARDS_GROUP)
+        .group(&PLAY_GROUP)
+        .group(&CASH_GROUP)
+        .group(&TOURNAMENTS_GROUP)
+        .group(&BANK_GROUP);
 
     // Get token from file
     let token_filenname = &env::args().collect::<Vec<String>>()[1];
     let token = fs::read_to_string(token_filenname)
         .expect("Couldn't read file!");
 
+    // Tables, tournaments and wallets are saved in the directory given as second argument
+    let data_dir = env::args().nth(2).unwrap_or_else(|| String::from("data"));
+    let data_dir = Path::new(&data_dir);
+    let table_store = Store::open(data_dir.join("tables"))
+        .expect("Couldn't open the tables directory!");
+    let director_store = Store::open(data_dir.join("tournaments"))
+        .expect("Couldn't open the tournaments directory!");
+    let wallet_store = Store::open(data_dir.join("wallets"))
+        .expect("Couldn't open the wallets directory!");
+
     // Login with a bot token
     let mut client = Client::builder(token)
-        .event_handler(Handler)
+        .event_handler(Handler { tasks_started: AtomicBool::new(false) })
         .framework(framework)
         .await
         .expect("Error creating client");
 
+    {
+        let mut data = client.data.write().await;
+        data.insert::<TableRegistry>(Arc::new(Mutex::new(Tables::new(table_store))));
+        data.insert::<DirectorRegistry>(Arc::new(Mutex::new(Directors::new(director_store))));
+        data.insert::<WalletRegistry>(Arc::new(Mutex::new(Wallets::new(wallet_store))));
+    }
+
     // start listening for events by starting a single shard
     if let Err(why) = client.start().await {
         println!("An error occurred while running the client: {:?}", why);
     }
 }
 
-async fn reply(ctx: &Context, msg: &Message, user: &User) -> Option<User> {
-    let message = msg.channel_id.await_reply(ctx)
-        .author_id(user.id)
-        .timeout(Duration::from_secs(20))
-        .await;
-
-    if let Some(ref msg) = message {
-        if let Err(why) = msg.react(ctx, '✅').await {
-            println!("REACT ERROR: {:?}", why);
-        }
-    }
-
-    message.map(|msg| msg.author.clone())
-}
-
-fn format_list(l: &[User]) -> Option<String> {
-    match l.len() {
-        0 => None,
-        1 => Some(format!("{}", l[0])),
-        2 => Some(format!("{} and {}", l[0], l[1])),
-        n => {
-            let mut result = String::from("");
-            for u in 0..(n-2) {
-                result.push_str(&format!("{}, ", l[u]));
-            }
-            result.push_str(&format!("{} and {}", l[n-2], l[n-1]));
-            Some(result)
-        }
-    }
-}
-
+// a!verify <server seed> <commitment> [entropy...]
 #[command]
-#[aliases("poker")]
-async fn play_poker(ctx: &Context, msg: &Message) -> CommandResult {
-
-    // Get players responses
-    let tasks : Vec<_> =
-        msg.mentions
-        .iter()
-        .map( |user| { reply(&ctx, &msg, user) })
-        .collect();
-
-    let players: Vec<User> =
-        futures::future::join_all(tasks)
-        .await
-        .into_iter()
-        .flatten()
-        .collect::<Vec<User>>();
-
-    println!("PLAYERS: {:?}", players.iter().map(|u| &u.name).collect::<Vec<_>>());
-
-    let mut names = format!("No one");
-    if let Some(str) =  format_list(&players) {
-        names = str;
-    }
-
-    if let Err(why) = msg.channel_id.say(ctx,format!("{} want(s) to play", names)).await {
-        println!("MESSAGE ERROR: {:?}", why);
-    }
-
-    if players.len() < 3 {
-        if let Err(why) = msg.channel_id
-            .say(ctx,format!(
-                    "{} players are not enough to play poker, need at least 3",
-                    players.len()
-                )).await {
-            println!("MESSAGE ERROR: {:?}", why);
+async fn verify(ctx: &Context, msg: &Message, mut args: Args) -> CommandResult {
+    let (seed, commitment) = match (args.single::<String>(), args.single::<String>()) {
+        (Ok(seed), Ok(commitment)) => (seed, commitment),
+        _ => {
+            msg.channel_id
+                .say(ctx, "Usage: a!verify <server seed> <commitment> [entropy...]")
+                .await?;
+            return Ok(());
         }
-        return Ok(());
-    }
-
+    };
+    let entropy: Vec<String> = args.iter::<String>().flatten().collect();
+
+    let response = match fair::verify(&seed, &entropy, &commitment) {
+        Ok(deck) => {
+            let cards: Vec<String> = deck.cards().iter().rev().map(|c| c.short()).collect();
+            format!(
+                "The commitment matches. Mixed with the entropy `{}`, the server seed dealt the deck in this order: {}",
+                entropy.join(" "),
+                cards.join(" "),
+            )
+        }
+        Err(why) => format!("Verification failed: {}", why),
+    };
+    msg.channel_id.say(ctx, response).await?;
     Ok(())
 }
 
+#[hook]
+async fn dispatch_error(ctx: &Context, msg: &Message, error: DispatchError) {
+    let response = match error {
+        DispatchError::LackingPermissions(_) => "You need to be an administrator to do this",
+        DispatchError::OnlyForGuilds => "This command only works in a server",
+        _ => return,
+    };
+    commands::say(ctx, msg.channel_id, response).await;
+}
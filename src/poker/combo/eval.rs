@@ -0,0 +1,148 @@
+use std::iter::FromIterator;
+
+use super::*;
+
+// A set of cards packed in a single integer, with 16 bits per suit and one bit
+// per rank inside them, the two being the lowest bit.
+#[derive(PartialEq, Eq, Clone, Copy, Debug, Default)]
+pub struct CardSet(u64);
+
+impl CardSet {
+    pub fn new() -> Self {
+        CardSet(0)
+    }
+
+    pub fn insert(&mut self, card: Card) {
+        self.0 |= 1 << (card.suit() as u64 * 16 + card.rank() as u64);
+    }
+
+    pub fn contains(&self, card: Card) -> bool {
+        self.0 & 1 << (card.suit() as u64 * 16 + card.rank() as u64) != 0
+    }
+
+    pub fn len(&self) -> u32 {
+        self.0.count_ones()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0 == 0
+    }
+
+    fn suit(&self, suit: usize) -> u16 {
+        (self.0 >> (suit * 16)) as u16 & 0x1fff
+    }
+}
+
+impl<'a> FromIterator<&'a Card> for CardSet {
+    fn from_iter<I: IntoIterator<Item = &'a Card>>(cards: I) -> Self {
+        let mut set = CardSet::new();
+        for card in cards {
+            set.insert(*card);
+        }
+        set
+    }
+}
+
+// For every set of ranks, one plus the index of the top rank of its best
+// straight, or 0 when there is none.
+static STRAIGHTS: [u8; 8192] = straight_table();
+
+const fn straight_table() -> [u8; 8192] {
+    let mut table = [0; 8192];
+    let mut ranks = 0;
+    while ranks < 8192 {
+        // Shift the ranks up by one to make room for the ace playing low.
+        let m = (ranks << 1) | (ranks >> 12);
+        let s = m & (m >> 1) & (m >> 2) & (m >> 3) & (m >> 4);
+        if s != 0 {
+            let low = 31 - (s as u32).leading_zeros();
+            table[ranks] = (low + 4) as u8;
+        }
+        ranks += 1;
+    }
+    table
+}
+
+fn straight(ranks: u16) -> Option<Rank> {
+    match STRAIGHTS[ranks as usize] {
+        0 => None,
+        i => Some(RANKS[i as usize - 1]),
+    }
+}
+
+fn top(ranks: u16) -> u16 {
+    1 << (15 - ranks.leading_zeros())
+}
+
+fn rank(bit: u16) -> Rank {
+    RANKS[bit.trailing_zeros() as usize]
+}
+
+// Highest ranks in the set, from the top down.
+fn top_ranks<const N: usize>(mut ranks: u16) -> [Rank; N] {
+    let mut res = [Rank::Two; N];
+    for r in res.iter_mut() {
+        let t = top(ranks);
+        *r = rank(t);
+        ranks &= !t;
+    }
+    res
+}
+
+// Finds the best combo of a set of five to seven cards without allocating.
+pub fn evaluate(set: CardSet) -> CardCombo {
+    let (s0, s1, s2, s3) = (set.suit(0), set.suit(1), set.suit(2), set.suit(3));
+    let ranks = s0 | s1 | s2 | s3;
+
+    let mut flush = None;
+    for suit in [s0, s1, s2, s3].iter() {
+        if suit.count_ones() >= 5 {
+            match straight(*suit) {
+                Some(Rank::Ace) => return CC::RoyalFlush,
+                Some(high) => return CC::StraightFlush(high),
+                None => flush = Some(*suit),
+            }
+        }
+    }
+
+    let quads = s0 & s1 & s2 & s3;
+    let three_or_more = (s0 & s1 & s2) | (s0 & s1 & s3) | (s0 & s2 & s3) | (s1 & s2 & s3);
+    let two_or_more = (s0 & s1) | (s0 & s2) | (s0 & s3) | (s1 & s2) | (s1 & s3) | (s2 & s3);
+    let trips = three_or_more & !quads;
+    let pairs = two_or_more & !three_or_more;
+
+    if quads != 0 {
+        let q = top(quads);
+        return CC::FourOfAKind(rank(q), rank(top(ranks & !q)));
+    }
+    if trips != 0 {
+        let t = top(trips);
+        let rest = (trips & !t) | pairs;
+        if rest != 0 {
+            return CC::FullHouse(rank(t), rank(top(rest)));
+        }
+    }
+    if let Some(suit) = flush {
+        let [a, b, c, d, e] = top_ranks(suit);
+        return CC::Flush(a, b, c, d, e);
+    }
+    if let Some(high) = straight(ranks) {
+        return CC::Straight(high);
+    }
+    if trips != 0 {
+        let t = top(trips);
+        let [a, b] = top_ranks(ranks & !t);
+        return CC::ThreeOfAKind(rank(t), a, b);
+    }
+    if pairs.count_ones() >= 2 {
+        let p1 = top(pairs);
+        let p2 = top(pairs & !p1);
+        return CC::TwoPairs(rank(p1), rank(p2), rank(top(ranks & !p1 & !p2)));
+    }
+    if pairs != 0 {
+        let [a, b, c] = top_ranks(ranks & !pairs);
+        return CC::Pair(rank(pairs), a, b, c);
+    }
+    let [a, b, c, d, e] = top_ranks(ranks);
+    CC::HighCard(a, b, c, d, e)
+}
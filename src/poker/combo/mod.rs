@@ -1,17 +1,29 @@
+use serde::{Deserialize, Serialize};
+
 use super::*;
 
+mod eval;
+
+pub use eval::{evaluate, CardSet};
+
 #[cfg(test)]
 mod test;
+#[cfg(test)]
+mod verify;
 
-#[derive(PartialEq, Eq, PartialOrd, Ord, Clone, Copy, Debug)]
+#[derive(PartialEq, Eq, PartialOrd, Ord, Clone, Copy, Debug, Serialize, Deserialize)]
 pub enum CardCombo {
     HighCard(Rank, Rank, Rank, Rank, Rank),
-    Pair(Rank, Rank),
+    // Rank of the pair followed by the three kickers.
+    Pair(Rank, Rank, Rank, Rank),
+    // High pair, low pair and kicker.
     TwoPairs(Rank, Rank, Rank),
-    ThreeOfAKind(Rank, Rank),
+    // Rank of the three of a kind followed by the two kickers.
+    ThreeOfAKind(Rank, Rank, Rank),
     Straight(Rank),
     Flush(Rank, Rank, Rank, Rank, Rank),
-    FullHouse(Rank),
+    // Rank of the three of a kind, then rank of the pair.
+    FullHouse(Rank, Rank),
     FourOfAKind(Rank, Rank),
     StraightFlush(Rank),
     RoyalFlush,
@@ -19,6 +31,51 @@ pub enum CardCombo {
 
 type CC = CardCombo;
 
+impl fmt::Display for CardCombo {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name = match self {
+            CC::HighCard(..) => "high card",
+            CC::Pair(..) => "a pair",
+            CC::TwoPairs(..) => "two pairs",
+            CC::ThreeOfAKind(..) => "three of a kind",
+            CC::Straight(..) => "a straight",
+            CC::Flush(..) => "a flush",
+            CC::FullHouse(..) => "a full house",
+            CC::FourOfAKind(..) => "four of a kind",
+            CC::StraightFlush(..) => "a straight flush",
+            CC::RoyalFlush => "a royal flush",
+        };
+        f.write_str(name)
+    }
+}
+
+#[derive(PartialEq, Eq, Clone, Copy, Debug)]
+pub enum Source {
+    Hand,
+    Board,
+}
+
+#[derive(PartialEq, Eq, Clone, Copy, Debug, Serialize, Deserialize)]
+pub struct BestHand {
+    pub combo: CardCombo,
+    // The five cards that play, the ones making the combo first.
+    pub cards: [Card; 5],
+}
+
+impl BestHand {
+    // Marks which of the five cards are the player's hole cards.
+    pub fn sources(&self, hand: &Hand) -> [Source; 5] {
+        let (c1, c2) = hand.cards();
+        let mut sources = [Source::Board; 5];
+        for (card, source) in self.cards.iter().zip(sources.iter_mut()) {
+            if *card == c1 || *card == c2 {
+                *source = Source::Hand;
+            }
+        }
+        sources
+    }
+}
+
 fn find_royal_flush(cards: &[Card]) -> Option<CardCombo> {
     if let Some(CC::StraightFlush(Rank::Ace)) = find_straight_flush(cards) {
         Some(CC::RoyalFlush) }
@@ -36,25 +93,11 @@ fn find_straight_flush(cards: &[Card]) -> Option<CardCombo> {
     if let Some((suit, _)) = suitcount
         .iter()
         .find(|(_,i)| **i >= 5) {
-            let mut flush_sorted = cards
+            let flush_ranks = cards
                 .iter()
                 .filter(|c| c.suit() == *suit)
-                .map(|c| c.rank())
-                .collect::<Vec<Rank>>();
-            flush_sorted.sort_by(|a, b| b.cmp(a));
-            let res =
-                flush_sorted
-                .windows(5)
-                .map(|s|
-                    (s[0],
-                    s
-                    .windows(2)
-                    .map(|is| (is[0], is[1]))
-                    .map(|(a, b)| a.is_next(b))
-                    .all(|i| i)
-                    ))
-                .find(|(r, b)| *b);
-            res.map(|(r,_)| CC::StraightFlush(r))
+                .map(|c| c.rank());
+            straight_high(flush_ranks).map(CC::StraightFlush)
     } else {
         None
     }
@@ -94,11 +137,11 @@ fn find_full_house(cards: &[Card]) -> Option<CardCombo> {
             sorted_cards.windows(3)
             .map(|s| (s[0], s[1], s[2]))
             .find(|(a, b, c)| a==b && b==c) {
-        if sorted_cards.windows(2)
+        if let Some((pair_rank, _)) = sorted_cards.windows(2)
                 .map(|s| (s[0], s[1]))
                 .filter(|(a, _)| *a != three_rank)
-                .any(|(a, b)| a==b) {
-            return Some(CC::FullHouse(three_rank));
+                .find(|(a, b)| a==b) {
+            return Some(CC::FullHouse(three_rank, pair_rank));
         }
     }
     None
@@ -132,22 +175,22 @@ fn find_flush(cards: &[Card]) -> Option<CardCombo> {
 }
 
 fn find_straight(cards: &[Card]) -> Option<CardCombo> {
-    let mut sorted_cards = cards.iter().map(|c| c.rank()).collect::<Vec<Rank>>();
-    sorted_cards.sort_by(|a, b| b.cmp(a));
-    println!("{:?}", sorted_cards);
-    let res =
-        sorted_cards
+    straight_high(cards.iter().map(|c| c.rank())).map(CC::Straight)
+}
+
+// Top rank of the highest straight among the given ranks. Repeated ranks are
+// ignored and the ace also counts below the two, making the wheel five high.
+fn straight_high<I: Iterator<Item = Rank>>(ranks: I) -> Option<Rank> {
+    let mut sorted_ranks = ranks.collect::<Vec<Rank>>();
+    sorted_ranks.sort_by(|a, b| b.cmp(a));
+    sorted_ranks.dedup();
+    if sorted_ranks.first() == Some(&Rank::Ace) {
+        sorted_ranks.push(Rank::Ace);
+    }
+    sorted_ranks
         .windows(5)
-        .map(|s|
-            (s[0],
-            s
-            .windows(2)
-            .map(|is| (is[0], is[1]))
-            .map(|(a, b)| a.is_next(b))
-            .all(|i| i)
-            ))
-        .find(|(r, b)| *b);
-    res.map(|(r, _)| CC::Straight(r))
+        .find(|s| s.windows(2).all(|is| is[0].is_next(is[1])))
+        .map(|s| s[0])
 }
 
 fn find_three_of_a_kind(cards: &[Card]) -> Option<CardCombo> {
@@ -161,13 +204,13 @@ fn find_three_of_a_kind(cards: &[Card]) -> Option<CardCombo> {
             sorted_cards.windows(3)
             .map(|s| (s[0], s[1], s[2]))
             .find(|(a, b, c)| a==b && b==c) {
-        let high_rank =
+        let kickers: Vec<Rank> =
             sorted_cards
-            .iter()
-            .filter(|r| **r != three_rank)
-            .max()
-            .unwrap();
-        Some(CC::ThreeOfAKind(three_rank, *high_rank))
+            .into_iter()
+            .filter(|r| *r != three_rank)
+            .take(2)
+            .collect();
+        Some(CC::ThreeOfAKind(three_rank, kickers[0], kickers[1]))
     } else {
         None
     }
@@ -216,13 +259,13 @@ fn find_pair(cards: &[Card]) -> Option<CardCombo> {
             sorted_cards.windows(2)
             .map(|s| (s[0], s[1]))
             .find(|(a, b)| a==b) {
-        let high_rank =
+        let kickers: Vec<Rank> =
             sorted_cards
-            .iter()
-            .filter(|r| **r != pair_rank)
-            .max()
-            .unwrap();
-        Some(CC::Pair(pair_rank, *high_rank))
+            .into_iter()
+            .filter(|r| *r != pair_rank)
+            .take(3)
+            .collect();
+        Some(CC::Pair(pair_rank, kickers[0], kickers[1], kickers[2]))
     } else {
         None
     }
@@ -245,6 +288,12 @@ fn find_high_card(cards: &[Card]) -> CardCombo {
 }
 
 pub fn find_best_card(cards: &[Card]) -> CardCombo {
+    evaluate(cards.iter().collect())
+}
+
+// Checks every combo one after the other. Much slower than `find_best_card`,
+// but simple enough to serve as a reference for it.
+pub fn find_best_card_naive(cards: &[Card]) -> CardCombo {
     find_royal_flush(cards)
         .or_else(|| find_straight_flush(cards))
         .or_else(|| find_four_of_a_kind(cards))
@@ -257,3 +306,42 @@ pub fn find_best_card(cards: &[Card]) -> CardCombo {
         .unwrap_or_else(|| find_high_card(cards))
 }
 
+
+// Finds the best five of the given cards, trying every combination of five.
+pub fn find_best_hand(cards: &[Card]) -> BestHand {
+    let n = cards.len();
+    let mut best: Option<BestHand> = None;
+    for a in 0..n {
+        for b in (a + 1)..n {
+            for c in (b + 1)..n {
+                for d in (c + 1)..n {
+                    for e in (d + 1)..n {
+                        let five = [cards[a], cards[b], cards[c], cards[d], cards[e]];
+                        let combo = find_best_card(&five);
+                        if best.is_none_or(|b| combo > b.combo) {
+                            best = Some(BestHand { combo, cards: arrange(five, combo) });
+                        }
+                    }
+                }
+            }
+        }
+    }
+    best.expect("at least five cards are needed to make a hand")
+}
+
+// Orders the cards by how many of their rank there are and then by rank,
+// so the cards making the combo come before the kickers.
+fn arrange(mut cards: [Card; 5], combo: CardCombo) -> [Card; 5] {
+    let ranks: Vec<Rank> = cards.iter().map(|c| c.rank()).collect();
+    let count = |r: Rank| ranks.iter().filter(|o| **o == r).count();
+    cards.sort_by(|a, b| {
+        count(b.rank())
+            .cmp(&count(a.rank()))
+            .then(b.rank().cmp(&a.rank()))
+    });
+    // The ace plays low in the wheel.
+    if let CC::Straight(Rank::Five) | CC::StraightFlush(Rank::Five) = combo {
+        cards.rotate_left(1);
+    }
+    cards
+}
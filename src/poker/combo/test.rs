@@ -13,9 +13,41 @@ fn orderinng() {
         CC::Flush(R::Jack, R::Six, R::Five, R::Four, R::Three)
     );
 
-    assert!(CC::Pair(R::Seven, R::Six) < CC::Pair(R::Eight, R::Four));
-    assert!(CC::Pair(R::Seven, R::Four) < CC::Pair(R::Seven, R::Six));
-    assert!(CC::Pair(R::Seven, R::Four) == CC::Pair(R::Seven, R::Four));
+    assert!(
+        CC::Pair(R::Seven, R::Six, R::Five, R::Four)
+        <
+        CC::Pair(R::Eight, R::Four, R::Three, R::Two)
+    );
+    assert!(
+        CC::Pair(R::Seven, R::Four, R::Three, R::Two)
+        <
+        CC::Pair(R::Seven, R::Six, R::Three, R::Two)
+    );
+    assert!(
+        CC::Pair(R::Seven, R::Ace, R::Four, R::Two)
+        <
+        CC::Pair(R::Seven, R::Ace, R::Four, R::Three)
+    );
+    assert!(
+        CC::Pair(R::Seven, R::Four, R::Three, R::Two)
+        ==
+        CC::Pair(R::Seven, R::Four, R::Three, R::Two)
+    );
+
+    assert!(
+        CC::ThreeOfAKind(R::Nine, R::King, R::Three)
+        <
+        CC::ThreeOfAKind(R::Nine, R::King, R::Four)
+    );
+    assert!(CC::FullHouse(R::Nine, R::Two) < CC::FullHouse(R::Nine, R::Three));
+    assert!(CC::FullHouse(R::Nine, R::Ace) < CC::FullHouse(R::Ten, R::Two));
+    assert!(CC::TwoPairs(R::Nine, R::Two, R::Ace) < CC::TwoPairs(R::Nine, R::Three, R::Four));
+    assert!(CC::FourOfAKind(R::Two, R::Ace) < CC::FourOfAKind(R::Three, R::Two));
+    assert!(
+        CC::HighCard(R::Ace, R::King, R::Queen, R::Jack, R::Nine)
+        <
+        CC::Pair(R::Two, R::Five, R::Four, R::Three)
+    );
 }
 
 #[cfg(test)]
@@ -74,7 +106,51 @@ mod combos {
             Card(S::Diamonds, R::Nine),
             Card(S::Hearts, R::Seven),
         ];
-        assert_eq!(Some(CC::FullHouse(R::Eight)), find_full_house(&cards));
+        assert_eq!(Some(CC::FullHouse(R::Eight, R::Nine)), find_full_house(&cards));
+    }
+
+    #[test]
+    fn full_house_from_two_trips() {
+        let cards = [
+            Card(S::Clubs, R::Eight),
+            Card(S::Hearts, R::Eight),
+            Card(S::Clubs, R::Nine),
+            Card(S::Diamonds, R::Eight),
+            Card(S::Spades, R::Nine),
+            Card(S::Diamonds, R::Nine),
+            Card(S::Hearts, R::Ace),
+        ];
+        assert_eq!(Some(CC::FullHouse(R::Nine, R::Eight)), find_full_house(&cards));
+    }
+
+    #[test]
+    fn two_pairs_from_three_pairs() {
+        let cards = [
+            Card(S::Clubs, R::King),
+            Card(S::Hearts, R::King),
+            Card(S::Clubs, R::Queen),
+            Card(S::Diamonds, R::Queen),
+            Card(S::Spades, R::Seven),
+            Card(S::Diamonds, R::Seven),
+            Card(S::Hearts, R::Two),
+        ];
+        assert_eq!(Some(CC::TwoPairs(R::King, R::Queen, R::Seven)), find_two_pairs(&cards));
+    }
+
+    #[test]
+    fn second_kicker_breaks_tie() {
+        let board = [
+            Card(S::Clubs, R::Ace),
+            Card(S::Hearts, R::Ace),
+            Card(S::Clubs, R::Jack),
+            Card(S::Diamonds, R::Six),
+            Card(S::Spades, R::Three),
+        ];
+        let mut first = board.to_vec();
+        first.extend(&[Card(S::Spades, R::King), Card(S::Hearts, R::Ten)]);
+        let mut second = board.to_vec();
+        second.extend(&[Card(S::Hearts, R::King), Card(S::Hearts, R::Nine)]);
+        assert!(find_best_card(&first) > find_best_card(&second));
     }
 
     #[test]
@@ -105,6 +181,78 @@ mod combos {
         assert_eq!(Some(CC::Straight(R::Nine)), find_straight(&cards));
     }
 
+    #[test]
+    fn straight_with_paired_ranks() {
+        let cards = [
+            Card(S::Clubs, R::Nine),
+            Card(S::Hearts, R::Nine),
+            Card(S::Spades, R::Eight),
+            Card(S::Diamonds, R::Seven),
+            Card(S::Hearts, R::Seven),
+            Card(S::Diamonds, R::Six),
+            Card(S::Hearts, R::Five),
+        ];
+        assert_eq!(Some(CC::Straight(R::Nine)), find_straight(&cards));
+    }
+
+    #[test]
+    fn wheel() {
+        let cards = [
+            Card(S::Clubs, R::Ace),
+            Card(S::Hearts, R::Two),
+            Card(S::Spades, R::Three),
+            Card(S::Diamonds, R::Four),
+            Card(S::Hearts, R::Four),
+            Card(S::Diamonds, R::Five),
+            Card(S::Hearts, R::King),
+        ];
+        assert_eq!(Some(CC::Straight(R::Five)), find_straight(&cards));
+        assert!(CC::Straight(R::Five) < CC::Straight(R::Six));
+    }
+
+    #[test]
+    fn six_high_beats_wheel() {
+        let cards = [
+            Card(S::Clubs, R::Ace),
+            Card(S::Hearts, R::Two),
+            Card(S::Spades, R::Three),
+            Card(S::Diamonds, R::Four),
+            Card(S::Hearts, R::Six),
+            Card(S::Diamonds, R::Five),
+            Card(S::Hearts, R::King),
+        ];
+        assert_eq!(Some(CC::Straight(R::Six)), find_straight(&cards));
+    }
+
+    #[test]
+    fn no_wrap_around() {
+        let cards = [
+            Card(S::Clubs, R::Queen),
+            Card(S::Hearts, R::King),
+            Card(S::Spades, R::Ace),
+            Card(S::Diamonds, R::Two),
+            Card(S::Hearts, R::Three),
+            Card(S::Diamonds, R::Eight),
+            Card(S::Hearts, R::Eight),
+        ];
+        assert_eq!(None, find_straight(&cards));
+    }
+
+    #[test]
+    fn steel_wheel() {
+        let cards = [
+            Card(S::Spades, R::Ace),
+            Card(S::Spades, R::Two),
+            Card(S::Spades, R::Three),
+            Card(S::Spades, R::Four),
+            Card(S::Hearts, R::Six),
+            Card(S::Spades, R::Five),
+            Card(S::Spades, R::King),
+        ];
+        assert_eq!(Some(CC::StraightFlush(R::Five)), find_straight_flush(&cards));
+        assert_eq!(CC::StraightFlush(R::Five), find_best_card(&cards));
+    }
+
     #[test]
     fn three_of_a_kind() {
         let cards = [
@@ -116,7 +264,7 @@ mod combos {
             Card(S::Diamonds, R::Six),
             Card(S::Hearts, R::Three),
         ];
-        assert_eq!(Some(CC::ThreeOfAKind(R::Eight, R::Nine)), find_three_of_a_kind(&cards));
+        assert_eq!(Some(CC::ThreeOfAKind(R::Eight, R::Nine, R::Six)), find_three_of_a_kind(&cards));
 
     }
 
@@ -145,7 +293,7 @@ mod combos {
             Card(S::Diamonds, R::Six),
             Card(S::Hearts, R::Eight),
         ];
-        assert_eq!(Some(CC::Pair(R::Eight, R::Nine)), find_pair(&cards));
+        assert_eq!(Some(CC::Pair(R::Eight, R::Nine, R::Six, R::Four)), find_pair(&cards));
     }
 
     #[test]
@@ -162,3 +310,117 @@ mod combos {
         assert_eq!(CC::HighCard(R::Nine, R::Eight, R::Seven, R::Six, R::Four), find_high_card(&cards)) 
     }
 }
+
+#[cfg(test)]
+mod best_hand {
+    use super::*;
+
+    #[test]
+    fn full_house_cards() {
+        let cards = [
+            Card(S::Clubs, R::Eight),
+            Card(S::Hearts, R::Eight),
+            Card(S::Clubs, R::Nine),
+            Card(S::Diamonds, R::Eight),
+            Card(S::Clubs, R::Four),
+            Card(S::Diamonds, R::Nine),
+            Card(S::Hearts, R::Seven),
+        ];
+        let best = find_best_hand(&cards);
+        assert_eq!(best.combo, find_best_card(&cards));
+        assert_eq!(best.cards, [
+            Card(S::Clubs, R::Eight),
+            Card(S::Hearts, R::Eight),
+            Card(S::Diamonds, R::Eight),
+            Card(S::Clubs, R::Nine),
+            Card(S::Diamonds, R::Nine),
+        ]);
+    }
+
+    #[test]
+    fn flush_cards() {
+        let cards = [
+            Card(S::Clubs, R::Eight),
+            Card(S::Hearts, R::Three),
+            Card(S::Clubs, R::Nine),
+            Card(S::Diamonds, R::Two),
+            Card(S::Clubs, R::Four),
+            Card(S::Clubs, R::Six),
+            Card(S::Clubs, R::Seven),
+        ];
+        let best = find_best_hand(&cards);
+        assert_eq!(best.combo, CC::Flush(R::Nine, R::Eight, R::Seven, R::Six, R::Four));
+        assert!(best.cards.iter().all(|c| c.suit() == S::Clubs));
+    }
+
+    #[test]
+    fn wheel_cards() {
+        let cards = [
+            Card(S::Clubs, R::Ace),
+            Card(S::Hearts, R::Two),
+            Card(S::Spades, R::Three),
+            Card(S::Diamonds, R::Four),
+            Card(S::Hearts, R::Four),
+            Card(S::Diamonds, R::Five),
+            Card(S::Hearts, R::King),
+        ];
+        let best = find_best_hand(&cards);
+        assert_eq!(best.combo, CC::Straight(R::Five));
+        let ranks: Vec<Rank> = best.cards.iter().map(|c| c.rank()).collect();
+        assert_eq!(ranks, vec![R::Five, R::Four, R::Three, R::Two, R::Ace]);
+    }
+
+    #[test]
+    fn sources() {
+        let hand = Hand(Card(S::Spades, R::Ace), Card(S::Hearts, R::Two));
+        let cards = [
+            Card(S::Spades, R::Ace),
+            Card(S::Hearts, R::Two),
+            Card(S::Clubs, R::Ace),
+            Card(S::Diamonds, R::King),
+            Card(S::Clubs, R::Nine),
+            Card(S::Diamonds, R::Six),
+            Card(S::Hearts, R::Four),
+        ];
+        let best = find_best_hand(&cards);
+        assert_eq!(best.combo, CC::Pair(R::Ace, R::King, R::Nine, R::Six));
+        assert_eq!(best.sources(&hand), [
+            Source::Hand,
+            Source::Board,
+            Source::Board,
+            Source::Board,
+            Source::Board,
+        ]);
+    }
+}
+
+#[cfg(test)]
+mod eval {
+    use super::*;
+    use rand::{rngs::StdRng, seq::SliceRandom, SeedableRng};
+
+    #[test]
+    fn card_set() {
+        let mut set = CardSet::new();
+        assert!(set.is_empty());
+        set.insert(Card(S::Spades, R::Ace));
+        set.insert(Card(S::Clubs, R::Two));
+        set.insert(Card(S::Clubs, R::Two));
+        assert_eq!(set.len(), 2);
+        assert!(set.contains(Card(S::Spades, R::Ace)));
+        assert!(!set.contains(Card(S::Hearts, R::Ace)));
+    }
+
+    #[test]
+    fn matches_naive() {
+        let mut rng = StdRng::seed_from_u64(7);
+        let mut deck: Vec<Card> = (0..52).filter_map(Card::from_index).collect();
+        for n in 5..=7 {
+            for _ in 0..20_000 {
+                deck.shuffle(&mut rng);
+                let cards = &deck[..n];
+                assert_eq!(find_best_card(cards), find_best_card_naive(cards), "{:?}", cards);
+            }
+        }
+    }
+}
@@ -0,0 +1,161 @@
+// Checks the evaluator against a brute-force reference that is easy to get
+// right: classify every five-card hand from its sorted rank counts, and take
+// the best of every five-card subset for bigger hands.
+use std::collections::{BTreeMap, BTreeSet};
+
+use rand::{rngs::StdRng, seq::SliceRandom, SeedableRng};
+
+use super::*;
+
+fn reference_five(cards: &[Card]) -> CardCombo {
+    let mut counts: BTreeMap<Rank, usize> = BTreeMap::new();
+    for c in cards {
+        *counts.entry(c.rank()).or_insert(0) += 1;
+    }
+    // Most repeated ranks first, higher ranks first among those.
+    let mut groups: Vec<(usize, Rank)> = counts.into_iter().map(|(r, n)| (n, r)).collect();
+    groups.sort_by(|a, b| b.cmp(a));
+    let shape: Vec<usize> = groups.iter().map(|(n, _)| *n).collect();
+    let r: Vec<Rank> = groups.iter().map(|(_, r)| *r).collect();
+
+    let is_flush = cards.iter().all(|c| c.suit() == cards[0].suit());
+    let straight = if shape.len() == 5 {
+        if r[0] as u8 - r[4] as u8 == 4 {
+            Some(r[0])
+        } else if r == [Rank::Ace, Rank::Five, Rank::Four, Rank::Three, Rank::Two] {
+            Some(Rank::Five)
+        } else {
+            None
+        }
+    } else {
+        None
+    };
+
+    match (straight, is_flush, &shape[..]) {
+        (Some(Rank::Ace), true, _) => CC::RoyalFlush,
+        (Some(high), true, _) => CC::StraightFlush(high),
+        (_, _, [4, 1]) => CC::FourOfAKind(r[0], r[1]),
+        (_, _, [3, 2]) => CC::FullHouse(r[0], r[1]),
+        (_, true, _) => CC::Flush(r[0], r[1], r[2], r[3], r[4]),
+        (Some(high), _, _) => CC::Straight(high),
+        (_, _, [3, 1, 1]) => CC::ThreeOfAKind(r[0], r[1], r[2]),
+        (_, _, [2, 2, 1]) => CC::TwoPairs(r[0], r[1], r[2]),
+        (_, _, [2, 1, 1, 1]) => CC::Pair(r[0], r[1], r[2], r[3]),
+        _ => CC::HighCard(r[0], r[1], r[2], r[3], r[4]),
+    }
+}
+
+fn reference(cards: &[Card]) -> CardCombo {
+    let n = cards.len();
+    let mut best = None;
+    for skip in 0..(1u32 << n) {
+        if n as u32 - skip.count_ones() != 5 {
+            continue;
+        }
+        let five: Vec<Card> = (0..n)
+            .filter(|i| skip & (1 << i) == 0)
+            .map(|i| cards[i])
+            .collect();
+        best = best.max(Some(reference_five(&five)));
+    }
+    best.unwrap()
+}
+
+fn category(combo: CardCombo) -> usize {
+    match combo {
+        CC::HighCard(..) => 0,
+        CC::Pair(..) => 1,
+        CC::TwoPairs(..) => 2,
+        CC::ThreeOfAKind(..) => 3,
+        CC::Straight(..) => 4,
+        CC::Flush(..) => 5,
+        CC::FullHouse(..) => 6,
+        CC::FourOfAKind(..) => 7,
+        CC::StraightFlush(..) => 8,
+        CC::RoyalFlush => 9,
+    }
+}
+
+fn all_cards() -> Vec<Card> {
+    (0..52).filter_map(Card::from_index).collect()
+}
+
+#[test]
+fn every_five_card_hand() {
+    let deck = all_cards();
+    let mut categories = [0u32; 10];
+    let mut distinct = BTreeSet::new();
+    let mut total = 0;
+    for a in 0..52 {
+        for b in (a + 1)..52 {
+            for c in (b + 1)..52 {
+                for d in (c + 1)..52 {
+                    for e in (d + 1)..52 {
+                        let cards = [deck[a], deck[b], deck[c], deck[d], deck[e]];
+                        let combo = evaluate(cards.iter().collect());
+                        assert_eq!(combo, reference_five(&cards), "{:?}", cards);
+                        categories[category(combo)] += 1;
+                        distinct.insert(combo);
+                        total += 1;
+                    }
+                }
+            }
+        }
+    }
+
+    assert_eq!(total, 2_598_960);
+    assert_eq!(categories, [
+        1_302_540,
+        1_098_240,
+        123_552,
+        54_912,
+        10_200,
+        5_108,
+        3_744,
+        624,
+        36,
+        4,
+    ]);
+    // Every hand belongs to one of the 7462 classes of equal strength.
+    assert_eq!(distinct.len(), 7462);
+}
+
+#[test]
+fn seeded_seven_card_hands() {
+    let mut rng = StdRng::seed_from_u64(2021);
+    let mut deck = all_cards();
+    for _ in 0..100_000 {
+        deck.shuffle(&mut rng);
+        let cards = &deck[..7];
+        let expected = reference(cards);
+        assert_eq!(find_best_card(cards), expected, "{:?}", cards);
+        assert_eq!(find_best_card_naive(cards), expected, "{:?}", cards);
+    }
+}
+
+#[test]
+fn seeded_six_card_hands() {
+    let mut rng = StdRng::seed_from_u64(1999);
+    let mut deck = all_cards();
+    for _ in 0..50_000 {
+        deck.shuffle(&mut rng);
+        let cards = &deck[..6];
+        assert_eq!(find_best_card(cards), reference(cards), "{:?}", cards);
+    }
+}
+
+#[test]
+fn ordering_matches_reference() {
+    // Comparing any two hands through the evaluator must agree with the reference.
+    let mut rng = StdRng::seed_from_u64(88);
+    let mut deck = all_cards();
+    for _ in 0..50_000 {
+        deck.shuffle(&mut rng);
+        let (first, second) = (&deck[..7], &deck[7..14]);
+        assert_eq!(
+            find_best_card(first).cmp(&find_best_card(second)),
+            reference(first).cmp(&reference(second)),
+            "{:?} {:?}", first, second
+        );
+    }
+}
@@ -0,0 +1,201 @@
+// Commit-reveal shuffling, so players can check the bot didn't pick the cards.
+//
+// Before a hand the bot draws a secret 32 byte server seed and posts
+// SHA-256(server_seed). Only then does it collect entropy from the players,
+// which it mixes with the server seed into the deck seed once the hand is
+// dealt:
+//
+//     deck_seed = SHA-256(server_seed || len(e1) || e1 || len(e2) || e2 ...)
+//
+// with lengths as 4 byte big endian integers. The deck is an ordered deck
+// shuffled with Fisher-Yates, drawing numbers from the stream of blocks
+// SHA-256(deck_seed || k), k being an 8 byte big endian counter, read 4 bytes
+// at a time as big endian integers and rejection sampled to stay uniform.
+//
+// The server seed is revealed after showdown with the entropy, so anyone can
+// check it against the commitment and deal the deck again. Hands dealt before
+// the seed was committed to ahead of the entropy were committed to with
+// SHA-256(server_seed || deck), the deck being the indices of its cards in
+// dealing order, which a!verify still accepts.
+use std::fmt;
+
+use rand::RngCore;
+use sha2::{Digest, Sha256};
+
+use super::*;
+
+#[cfg(test)]
+mod test;
+
+// Most pieces of entropy mixed into a deck, and the longest one
+pub const MAX_ENTROPY: usize = 32;
+pub const MAX_ENTROPY_LEN: usize = 64;
+
+#[derive(PartialEq, Eq, Clone, Copy, Debug)]
+pub enum FairError {
+    InvalidSeed,
+    InvalidCommitment,
+    CommitmentMismatch,
+}
+
+impl fmt::Display for FairError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            FairError::InvalidSeed =>
+                write!(f, "the seed must be 64 hexadecimal characters"),
+            FairError::InvalidCommitment =>
+                write!(f, "the commitment must be 64 hexadecimal characters"),
+            FairError::CommitmentMismatch =>
+                write!(f, "the seed and entropy don't match the commitment"),
+        }
+    }
+}
+
+impl std::error::Error for FairError {}
+
+#[derive(PartialEq, Eq, Clone, Debug, Serialize, Deserialize)]
+pub struct Shuffle {
+    #[serde(with = "seed")]
+    pub server_seed: [u8; 32],
+    pub entropy: Vec<String>,
+}
+
+impl Shuffle {
+    pub fn new(entropy: Vec<String>) -> Self {
+        let mut server_seed = [0; 32];
+        rand::thread_rng().fill_bytes(&mut server_seed);
+        Shuffle { server_seed, entropy }
+    }
+
+    pub fn deck_seed(&self) -> [u8; 32] {
+        let mut hasher = Sha256::new();
+        hasher.update(self.server_seed);
+        for e in &self.entropy {
+            hasher.update((e.len() as u32).to_be_bytes());
+            hasher.update(e.as_bytes());
+        }
+        hasher.finalize().into()
+    }
+
+    pub fn deck(&self) -> Deck {
+        let mut stream = HashStream::new(self.deck_seed());
+        let mut deck = Deck::ordered();
+        for i in (1..deck.cards.len()).rev() {
+            let j = stream.below(i as u32 + 1) as usize;
+            deck.cards.swap(i, j);
+        }
+        deck
+    }
+
+    // Mixes a player's input into the deck, without whitespace so it can be
+    // passed back to a!verify. False when there was nothing to add or the
+    // shuffle already has enough.
+    pub fn add_entropy(&mut self, input: &str) -> bool {
+        let input: String = input.split_whitespace().flat_map(str::chars).take(MAX_ENTROPY_LEN).collect();
+        if input.is_empty() || self.entropy.len() >= MAX_ENTROPY {
+            return false;
+        }
+        self.entropy.push(input);
+        true
+    }
+
+    // Posted before any entropy is collected
+    pub fn commitment(&self) -> [u8; 32] {
+        Sha256::digest(self.server_seed).into()
+    }
+
+    // Commitment of hands dealt before seeds were committed to ahead of time
+    pub fn deck_commitment(&self) -> [u8; 32] {
+        let deck = self.deck();
+        let mut hasher = Sha256::new();
+        hasher.update(self.server_seed);
+        hasher.update(dealing_order(&deck));
+        hasher.finalize().into()
+    }
+}
+
+// Card indices from the top of the deck to the bottom.
+pub fn dealing_order(deck: &Deck) -> Vec<u8> {
+    deck.cards().iter().rev().map(|c| c.index()).collect()
+}
+
+// Checks a revealed server seed against the commitment posted before the
+// hand and recomputes the deck it was dealt from with the entropy.
+pub fn verify(server_seed: &str, entropy: &[String], commitment: &str) -> Result<Deck, FairError> {
+    let server_seed = from_hex(server_seed).ok_or(FairError::InvalidSeed)?;
+    let commitment = from_hex(commitment).ok_or(FairError::InvalidCommitment)?;
+    let shuffle = Shuffle { server_seed, entropy: entropy.to_vec() };
+    if shuffle.commitment() == commitment || shuffle.deck_commitment() == commitment {
+        Ok(shuffle.deck())
+    } else {
+        Err(FairError::CommitmentMismatch)
+    }
+}
+
+pub fn to_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+pub fn from_hex(s: &str) -> Option<[u8; 32]> {
+    if s.len() != 64 || !s.is_ascii() {
+        return None;
+    }
+    let mut bytes = [0; 32];
+    for (i, b) in bytes.iter_mut().enumerate() {
+        *b = u8::from_str_radix(&s[i * 2..i * 2 + 2], 16).ok()?;
+    }
+    Some(bytes)
+}
+
+// Server seeds are stored as hex, the way they are revealed.
+mod seed {
+    use serde::{de::Error, Deserialize, Deserializer, Serializer};
+
+    pub fn serialize<S: Serializer>(seed: &[u8; 32], serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&super::to_hex(seed))
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<[u8; 32], D::Error> {
+        let s = String::deserialize(deserializer)?;
+        super::from_hex(&s).ok_or_else(|| D::Error::custom("invalid server seed"))
+    }
+}
+
+struct HashStream {
+    seed: [u8; 32],
+    counter: u64,
+    block: [u8; 32],
+    used: usize,
+}
+
+impl HashStream {
+    fn new(seed: [u8; 32]) -> Self {
+        HashStream { seed, counter: 0, block: [0; 32], used: 32 }
+    }
+
+    fn next_u32(&mut self) -> u32 {
+        if self.used == 32 {
+            let mut hasher = Sha256::new();
+            hasher.update(self.seed);
+            hasher.update(self.counter.to_be_bytes());
+            self.block = hasher.finalize().into();
+            self.counter += 1;
+            self.used = 0;
+        }
+        let mut word = [0; 4];
+        word.copy_from_slice(&self.block[self.used..self.used + 4]);
+        self.used += 4;
+        u32::from_be_bytes(word)
+    }
+
+    // Uniform number in 0..n, skipping the values that would favour low numbers.
+    fn below(&mut self, n: u32) -> u32 {
+        let zone = u32::MAX - u32::MAX % n;
+        loop {
+            let x = self.next_u32();
+            if x < zone {
+                return x % n;
+            }
+        }
+    }
+}
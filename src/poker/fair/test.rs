@@ -0,0 +1,114 @@
+use super::*;
+
+fn shuffle() -> Shuffle {
+    Shuffle {
+        server_seed: [7; 32],
+        entropy: vec![String::from("alice"), String::from("bob")],
+    }
+}
+
+#[test]
+fn deck_is_a_permutation() {
+    let mut order = dealing_order(&shuffle().deck());
+    assert_ne!(order, dealing_order(&Deck::ordered()));
+    order.sort_unstable();
+    assert_eq!(order, (0..52).collect::<Vec<u8>>());
+}
+
+#[test]
+fn entropy_changes_the_deck() {
+    let mut other = shuffle();
+    other.entropy[1].push('!');
+    assert_ne!(shuffle().deck().cards(), other.deck().cards());
+    assert_ne!(shuffle().deck_commitment(), other.deck_commitment());
+
+    // Entropy boundaries are part of the seed.
+    let joined = Shuffle {
+        server_seed: [7; 32],
+        entropy: vec![String::from("alicebob")],
+    };
+    assert_ne!(shuffle().deck_seed(), joined.deck_seed());
+}
+
+#[test]
+fn player_entropy_changes_the_deck() {
+    // The commitment is posted before players add their entropy, the same
+    // server seed then deals a different deck for every input.
+    let (mut first, mut second) = (shuffle(), shuffle());
+    let commitment = first.commitment();
+    assert!(first.add_entropy("1052711093452623912"));
+    assert!(second.add_entropy("lucky river"));
+    assert_eq!(first.commitment(), commitment);
+    assert_eq!(second.commitment(), commitment);
+    assert_ne!(first.deck().cards(), second.deck().cards());
+    assert_ne!(first.deck().cards(), shuffle().deck().cards());
+    assert_eq!(second.entropy[2], "luckyriver");
+
+    assert!(!second.add_entropy(" \t"));
+    assert!(second.add_entropy(&"x".repeat(100)));
+    assert_eq!(second.entropy[3].len(), MAX_ENTROPY_LEN);
+    while second.entropy.len() < MAX_ENTROPY {
+        second.add_entropy("more");
+    }
+    assert!(!second.add_entropy("too much"));
+}
+
+#[test]
+fn known_commitment() {
+    // Pinned so any change to the scheme is noticed, since it would break the
+    // verification of hands dealt before it.
+    assert_eq!(
+        to_hex(&shuffle().commitment()),
+        "4bb06f8e4e3a7715d201d573d0aa423762e55dabd61a2c02278fa56cc6d294e0"
+    );
+    assert_eq!(
+        to_hex(&shuffle().deck_commitment()),
+        "0b61f56612ae81655cd61af9d7ccbba042bd7b2a70d00538951e965e4e5477c4"
+    );
+    assert_eq!(&dealing_order(&shuffle().deck())[..5], &[31, 7, 27, 6, 36]);
+}
+
+#[test]
+fn verify_revealed_seed() {
+    let s = Shuffle::new(vec![String::from("1234"), String::from("abc")]);
+    let seed = to_hex(&s.server_seed);
+    let commitment = to_hex(&s.commitment());
+
+    let deck = verify(&seed, &s.entropy, &commitment).unwrap();
+    assert_eq!(deck.cards(), s.deck().cards());
+
+    // Other entropy deals another deck from the same seed.
+    let other = verify(&seed, &[String::from("1234")], &commitment).unwrap();
+    assert_ne!(other.cards(), s.deck().cards());
+    assert_eq!(
+        verify(&to_hex(&[1; 32]), &s.entropy, &commitment).unwrap_err(),
+        FairError::CommitmentMismatch
+    );
+
+    // Older hands committed to the deck, and so to the entropy as well.
+    let deck_commitment = to_hex(&s.deck_commitment());
+    assert_eq!(verify(&seed, &s.entropy, &deck_commitment).unwrap().cards(), s.deck().cards());
+    assert_eq!(
+        verify(&seed, &[String::from("1234")], &deck_commitment).unwrap_err(),
+        FairError::CommitmentMismatch
+    );
+    assert_eq!(verify("12", &s.entropy, &commitment).unwrap_err(), FairError::InvalidSeed);
+    assert_eq!(verify(&seed, &s.entropy, "zz").unwrap_err(), FairError::InvalidCommitment);
+}
+
+#[test]
+fn hex() {
+    let bytes = [0xab; 32];
+    assert_eq!(from_hex(&to_hex(&bytes)), Some(bytes));
+    assert_eq!(from_hex(&"g".repeat(64)), None);
+}
+
+#[test]
+fn deal_a_fair_hand() {
+    let s = shuffle();
+    let mut game = Game::new(&[1, 2], 100, Blinds::new(1, 2));
+    game.new_hand_with_deck(s.deck()).unwrap();
+    let order = dealing_order(&s.deck());
+    let (c1, c2) = game.players.get_player(1).unwrap().hand.unwrap().cards();
+    assert_eq!((c1.index(), c2.index()), (order[0], order[1]));
+}
@@ -0,0 +1,570 @@
+use std::cmp;
+use std::fmt;
+
+use rand::{rngs::StdRng, Rng, SeedableRng};
+use serde::{Deserialize, Serialize};
+
+use super::*;
+use super::pot::{build_pots, Contribution, Pot};
+use super::showdown::{resolve, Payout};
+use super::structure::{Betting, Limits, Structure};
+
+#[cfg(test)]
+mod test;
+
+#[derive(PartialEq, Eq, PartialOrd, Ord, Clone, Copy, Debug, Serialize, Deserialize)]
+pub enum Street {
+    Preflop,
+    Flop,
+    Turn,
+    River,
+    Showdown
+}
+
+impl Street {
+    fn next(self) -> Self {
+        match self {
+            Street::Preflop => Street::Flop,
+            Street::Flop => Street::Turn,
+            Street::Turn => Street::River,
+            Street::River => Street::Showdown,
+            Street::Showdown => Street::Showdown
+        }
+    }
+}
+
+impl fmt::Display for Street {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{:?}", self)
+    }
+}
+
+#[derive(PartialEq, Eq, Clone, Copy, Debug, Serialize, Deserialize)]
+pub enum Action {
+    Fold,
+    Check,
+    Call,
+    // Opens the betting round with a bet of the given size.
+    Bet(u32),
+    // Raises the total bet of the round to the given amount.
+    Raise(u32),
+    AllIn,
+}
+
+#[derive(PartialEq, Eq, Clone, Copy, Debug)]
+pub enum GameError {
+    NotEnoughPlayers,
+    HandOver,
+    UnknownPlayer,
+    NotYourTurn,
+    CannotCheck { to_call: u32 },
+    NothingToCall,
+    AlreadyBet,
+    NothingToRaise,
+    BetTooSmall { min: u32 },
+    RaiseTooSmall { min: u32 },
+    BetTooBig { max: u32 },
+    RaiseTooBig { max: u32 },
+    BettingCapped,
+    NotEnoughMoney { stack: u32 },
+    NotReopened,
+    HandInProgress,
+    AlreadySeated,
+}
+
+impl fmt::Display for GameError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            GameError::NotEnoughPlayers =>
+                write!(f, "at least 2 players with chips are needed to play a hand"),
+            GameError::HandOver =>
+                write!(f, "there is no hand in progress"),
+            GameError::UnknownPlayer =>
+                write!(f, "you are not seated at this table"),
+            GameError::NotYourTurn =>
+                write!(f, "it is not your turn"),
+            GameError::CannotCheck { to_call } =>
+                write!(f, "you can't check, there are {} chips to call", to_call),
+            GameError::NothingToCall =>
+                write!(f, "there is no bet to call"),
+            GameError::AlreadyBet =>
+                write!(f, "there is already a bet, raise instead"),
+            GameError::NothingToRaise =>
+                write!(f, "there is no bet to raise, bet instead"),
+            GameError::BetTooSmall { min } =>
+                write!(f, "the minimum bet is {}", min),
+            GameError::RaiseTooSmall { min } =>
+                write!(f, "the minimum raise is to {}", min),
+            GameError::BetTooBig { max } =>
+                write!(f, "the maximum bet is {}", max),
+            GameError::RaiseTooBig { max } =>
+                write!(f, "the maximum raise is to {}", max),
+            GameError::BettingCapped =>
+                write!(f, "the betting is capped, you can only call or fold"),
+            GameError::NotEnoughMoney { stack } =>
+                write!(f, "you only have {} chips", stack),
+            GameError::NotReopened =>
+                write!(f, "the all-in since you acted is less than a full raise, you can only call or fold"),
+            GameError::HandInProgress =>
+                write!(f, "players in the hand can only leave once it is over"),
+            GameError::AlreadySeated =>
+                write!(f, "the player is already seated"),
+        }
+    }
+}
+
+impl std::error::Error for GameError {}
+
+#[derive(PartialEq, Eq, Clone, Copy, Debug, Serialize, Deserialize)]
+pub struct Blinds {
+    pub small: u32,
+    pub big: u32,
+    pub ante: u32,
+    // When set, the big blind posts a single ante for the whole table.
+    pub big_blind_ante: bool,
+}
+
+impl Blinds {
+    pub fn new(small: u32, big: u32) -> Self {
+        Blinds {
+            small,
+            big,
+            ante: 0,
+            big_blind_ante: false,
+        }
+    }
+
+    pub fn with_ante(mut self, ante: u32) -> Self {
+        self.ante = ante;
+        self.big_blind_ante = false;
+        self
+    }
+
+    pub fn with_big_blind_ante(mut self, ante: u32) -> Self {
+        self.ante = ante;
+        self.big_blind_ante = true;
+        self
+    }
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Game {
+    // Seeds the deck of every hand, so a whole game can be replayed from the
+    // seed it was created with.
+    #[serde(with = "ids")]
+    pub seed: u64,
+    // Seed of the current hand's deck, enough to replay it with `Deck::from_seed`.
+    #[serde(with = "ids")]
+    pub hand_seed: u64,
+    pub deck: Deck,
+    // The whole board is dealt with the hand and revealed street by street.
+    pub board: Option<Board>,
+    pub players: PlayerRing,
+    // Highest bet of the current betting round.
+    pub bet: u32,
+    // Minimum amount a raise has to add on top of `bet`.
+    pub min_raise: u32,
+    // Bets and full raises of the current betting round, the big blind counting
+    // as the first bet preflop.
+    #[serde(default)]
+    pub raises: u32,
+    pub blinds: Blinds,
+    #[serde(default)]
+    pub structure: Structure,
+    #[serde(with = "ids::option")]
+    pub small_blind_player: Option<u64>,
+    #[serde(with = "ids::option")]
+    pub big_blind_player: Option<u64>,
+    pub hand_number: u32,
+    pub street: Street,
+    pub over: bool,
+    // Pots won in the last finished hand.
+    pub payouts: Vec<Payout>,
+}
+
+impl Game {
+    pub fn new(players: &[u64], starting_money: u32, blinds: Blinds) -> Self {
+        Self::with_seed(players, starting_money, blinds, rand::random())
+    }
+
+    pub fn with_seed(players: &[u64], starting_money: u32, blinds: Blinds, seed: u64) -> Self {
+        Game {
+            seed,
+            hand_seed: 0,
+            deck: Deck::ordered(),
+            board: None,
+            players: PlayerRing::new(players, starting_money),
+            bet: 0,
+            min_raise: blinds.big,
+            raises: 0,
+            blinds,
+            structure: Structure::NoLimit,
+            small_blind_player: None,
+            big_blind_player: None,
+            hand_number: 0,
+            street: Street::Preflop,
+            over: true,
+            payouts: Vec::new(),
+        }
+    }
+
+    pub fn with_structure(mut self, structure: Structure) -> Self {
+        self.structure = structure;
+        self
+    }
+
+    // Seats a player, dealt in from the next hand on.
+    pub fn seat(&mut self, id: u64, money: u32) -> Result<(), GameError> {
+        if !self.players.add(id, money) {
+            return Err(GameError::AlreadySeated);
+        }
+        Ok(())
+    }
+
+    // Takes a player and their chips off the table, in the middle of a hand
+    // only if they weren't dealt in.
+    pub fn unseat(&mut self, id: u64) -> Result<Player, GameError> {
+        let player = self.players.get_player(id).ok_or(GameError::UnknownPlayer)?;
+        if !self.over && (player.is_playing || player.committed > 0) {
+            return Err(GameError::HandInProgress);
+        }
+        Ok(self.players.remove(id).expect("the player is seated"))
+    }
+
+    pub fn new_hand(&mut self) -> Result<(), GameError> {
+        let seed = StdRng::seed_from_u64(self.seed.wrapping_add(self.hand_number as u64)).gen();
+        self.new_hand_with_deck(Deck::from_seed(seed))?;
+        self.hand_seed = seed;
+        Ok(())
+    }
+
+    // Deals the hand from a deck shuffled elsewhere, like a provably fair one.
+    pub fn new_hand_with_deck(&mut self, deck: Deck) -> Result<(), GameError> {
+        if self.players.iter().filter(|p| p.money > 0 && !p.sitting_out).count() < 2 {
+            return Err(GameError::NotEnoughPlayers);
+        }
+
+        self.deck = deck;
+        for p in self.players.iter_mut() {
+            p.is_playing = p.money > 0 && !p.sitting_out;
+            p.hand = if p.is_playing { Hand::new(&mut self.deck) } else { None };
+            p.bet = 0;
+            p.committed = 0;
+            p.has_acted = false;
+            p.acted_at = None;
+        }
+        self.board = Board::new(&mut self.deck);
+        self.street = Street::Preflop;
+        self.over = false;
+        self.payouts.clear();
+
+        if self.hand_number > 0 || !self.players.button().is_playing {
+            self.players.move_button();
+        }
+        self.hand_number += 1;
+        self.post_blinds();
+
+        if self.round_complete() {
+            self.next_street();
+        }
+        Ok(())
+    }
+
+    fn post_blinds(&mut self) {
+        let button = self.players.button().id;
+        let dealt = self.players.iter().filter(|p| p.is_playing).count();
+        // Heads-up the button posts the small blind and acts first preflop.
+        let small = if dealt == 2 && self.players.button().is_playing {
+            button
+        } else {
+            self.next_in_hand(button)
+        };
+        let big = self.next_in_hand(small);
+        self.small_blind_player = Some(small);
+        self.big_blind_player = Some(big);
+
+        let blinds = self.blinds;
+        if blinds.ante > 0 && !blinds.big_blind_ante {
+            for p in self.players.iter_mut().filter(|p| p.is_playing) {
+                p.post_ante(blinds.ante);
+            }
+        }
+        for (id, blind) in [(small, blinds.small), (big, blinds.big)].iter() {
+            let player = self.player_mut(*id);
+            let amount = (*blind).min(player.money);
+            player.put_in(amount);
+        }
+        // The big blind's ante comes out of what is left after their blind.
+        if blinds.big_blind_ante {
+            self.player_mut(big).post_ante(blinds.ante);
+        }
+
+        self.bet = blinds.big;
+        self.min_raise = blinds.big;
+        self.raises = 1;
+        let first = self.players.next_after(big, |p| p.can_act()).map(|p| p.id);
+        if let Some(id) = first {
+            self.players.set_current(id);
+        }
+    }
+
+    fn next_in_hand(&self, id: u64) -> u64 {
+        self.players
+            .next_after(id, |p| p.is_playing)
+            .map(|p| p.id)
+            .unwrap_or(id)
+    }
+
+    pub fn current_player(&self) -> Option<&Player> {
+        if self.over {
+            None
+        } else {
+            Some(self.players.current())
+        }
+    }
+
+    pub fn to_call(&self, id: u64) -> u32 {
+        self.players
+            .get_player(id)
+            .map(|p| cmp::min(self.bet - p.bet, p.money))
+            .unwrap_or(0)
+    }
+
+    pub fn pot(&self) -> u32 {
+        self.players.iter().map(|p| p.committed).sum()
+    }
+
+    // Main pot first, then the side pots created by all-in players.
+    pub fn pots(&self) -> Vec<Pot> {
+        let contributions: Vec<Contribution> = self.players
+            .iter()
+            .map(|p| Contribution { id: p.id, amount: p.committed, in_hand: p.is_playing })
+            .collect();
+        build_pots(&contributions)
+    }
+
+    pub fn visible_board(&self) -> Vec<Card> {
+        let shown = match self.street {
+            Street::Preflop => 0,
+            Street::Flop => 3,
+            Street::Turn => 4,
+            Street::River | Street::Showdown => 5,
+        };
+        self.board
+            .map(|b| b.cards()[..shown].to_vec())
+            .unwrap_or_default()
+    }
+
+    pub fn act(&mut self, id: u64, action: Action) -> Result<(), GameError> {
+        if self.over {
+            return Err(GameError::HandOver);
+        }
+        let player = *self.players.get_player(id).ok_or(GameError::UnknownPlayer)?;
+        if self.players.current().id != id {
+            return Err(GameError::NotYourTurn);
+        }
+
+        let to_call = self.bet - player.bet;
+        let stack = player.money;
+        match action {
+            Action::Fold => {
+                self.player_mut(id).is_playing = false;
+            }
+            Action::Check => {
+                if to_call > 0 {
+                    return Err(GameError::CannotCheck { to_call });
+                }
+            }
+            Action::Call => {
+                if to_call == 0 {
+                    return Err(GameError::NothingToCall);
+                }
+                self.player_mut(id).put_in(cmp::min(to_call, stack));
+            }
+            Action::Bet(amount) => {
+                if self.bet > 0 {
+                    return Err(GameError::AlreadyBet);
+                }
+                if amount > stack {
+                    return Err(GameError::NotEnoughMoney { stack });
+                }
+                if !self.can_raise(&player) {
+                    return Err(GameError::NotReopened);
+                }
+                let limits = self.limits(id).ok_or(GameError::NotEnoughMoney { stack })?;
+                if amount < limits.min {
+                    return Err(GameError::BetTooSmall { min: limits.min });
+                }
+                if amount > limits.max {
+                    return Err(GameError::BetTooBig { max: limits.max });
+                }
+                self.raise_to(id, amount);
+            }
+            Action::Raise(to) => {
+                if self.bet == 0 {
+                    return Err(GameError::NothingToRaise);
+                }
+                if to > player.bet + stack {
+                    return Err(GameError::NotEnoughMoney { stack });
+                }
+                if !self.can_raise(&player) {
+                    return Err(GameError::NotReopened);
+                }
+                let limits = match self.limits(id) {
+                    Some(limits) => limits,
+                    None if player.bet + stack > self.bet => return Err(GameError::BettingCapped),
+                    None => return Err(GameError::RaiseTooSmall { min: self.bet + self.min_raise }),
+                };
+                if to < limits.min {
+                    return Err(GameError::RaiseTooSmall { min: limits.min });
+                }
+                if to > limits.max {
+                    return Err(GameError::RaiseTooBig { max: limits.max });
+                }
+                self.raise_to(id, to);
+            }
+            Action::AllIn => {
+                let to = player.bet + stack;
+                if to > self.bet {
+                    if !self.can_raise(&player) {
+                        return Err(GameError::NotReopened);
+                    }
+                    match self.limits(id) {
+                        Some(limits) if to <= limits.max => self.raise_to(id, to),
+                        Some(limits) if self.bet == 0 => return Err(GameError::BetTooBig { max: limits.max }),
+                        Some(limits) => return Err(GameError::RaiseTooBig { max: limits.max }),
+                        None => return Err(GameError::BettingCapped),
+                    }
+                } else {
+                    self.player_mut(id).put_in(stack);
+                }
+            }
+        }
+
+        let bet = self.bet;
+        let player = self.player_mut(id);
+        player.has_acted = true;
+        player.acted_at = Some(bet);
+        self.after_action();
+        Ok(())
+    }
+
+    // How much the player may bet or raise to, None when they can only call,
+    // check or fold.
+    pub fn limits(&self, id: u64) -> Option<Limits> {
+        let player = self.players.get_player(id)?;
+        if !self.can_raise(player) {
+            return None;
+        }
+        self.structure.limits(&Betting {
+            street: self.street,
+            big_blind: self.blinds.big,
+            bet: self.bet,
+            min_raise: self.min_raise,
+            raises: self.raises,
+            pot: self.pot(),
+            player_bet: player.bet,
+            stack: player.money,
+        })
+    }
+
+    // Once a player acted, only raises adding up to a full raise over the bet
+    // they acted on give them the option to raise again: all-ins for less
+    // don't reopen the betting on their own.
+    fn can_raise(&self, player: &Player) -> bool {
+        match player.acted_at {
+            Some(bet) => self.bet >= bet + self.min_raise,
+            None => true,
+        }
+    }
+
+    fn player_mut(&mut self, id: u64) -> &mut Player {
+        self.players.get_player_mut(id).expect("player is seated")
+    }
+
+    fn raise_to(&mut self, id: u64, to: u32) {
+        let player = self.player_mut(id);
+        let amount = to - player.bet;
+        player.put_in(amount);
+
+        let raise = to - self.bet;
+        if raise >= self.min_raise {
+            self.min_raise = raise;
+            self.raises += 1;
+        }
+        self.bet = to;
+        for p in self.players.iter_mut().filter(|p| p.id != id) {
+            p.has_acted = false;
+        }
+    }
+
+    fn after_action(&mut self) {
+        if self.players.iter().filter(|p| p.is_playing).count() == 1 {
+            self.finish_hand();
+        } else if self.round_complete() {
+            self.next_street();
+        } else {
+            self.players.advance();
+        }
+    }
+
+    fn round_complete(&self) -> bool {
+        let acting: Vec<&Player> = self.players.iter().filter(|p| p.can_act()).collect();
+        // A lone player who already covers every all-in has nobody to bet against.
+        if let [last] = acting[..] {
+            let highest = self.players
+                .iter()
+                .filter(|p| p.is_playing && p.id != last.id)
+                .map(|p| p.bet)
+                .max()
+                .unwrap_or(0);
+            if last.bet >= highest {
+                return true;
+            }
+        }
+        acting.iter().all(|p| p.has_acted && p.bet == self.bet)
+    }
+
+    // After the flop the first player left of the button starts the betting.
+    fn first_to_act(&mut self) {
+        let button = self.players.button().id;
+        let first = self.players.next_after(button, |p| p.can_act()).map(|p| p.id);
+        if let Some(id) = first {
+            self.players.set_current(id);
+        }
+    }
+
+    fn next_street(&mut self) {
+        loop {
+            for p in self.players.iter_mut() {
+                p.bet = 0;
+                p.has_acted = false;
+                p.acted_at = None;
+            }
+            self.bet = 0;
+            self.min_raise = self.blinds.big;
+            self.raises = 0;
+            self.street = self.street.next();
+
+            if self.street == Street::Showdown {
+                self.finish_hand();
+                return;
+            }
+            // With less than two players left to act there is nothing to bet on,
+            // so the rest of the board is dealt straight away.
+            if self.players.iter().filter(|p| p.can_act()).count() > 1 {
+                self.first_to_act();
+                return;
+            }
+        }
+    }
+
+    fn finish_hand(&mut self) {
+        let pots = self.pots();
+        self.payouts = resolve(&self.players, self.board, &pots);
+        for payout in self.payouts.clone() {
+            self.player_mut(payout.id).money += payout.amount;
+        }
+        self.over = true;
+    }
+}
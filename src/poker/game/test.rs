@@ -0,0 +1,509 @@
+use super::*;
+
+type R = Rank;
+type S = Suit;
+
+// Player 1 is on the button, 2 posts the small blind and 3 the big blind.
+fn game() -> Game {
+    let mut game = Game::new(&[1, 2, 3], 1000, Blinds::new(5, 10));
+    game.new_hand().unwrap();
+    game
+}
+
+fn rig(game: &mut Game, hands: &[(u64, Hand)], board: Board) {
+    for (id, hand) in hands {
+        game.players.get_player_mut(*id).unwrap().hand = Some(*hand);
+    }
+    game.board = Some(board);
+}
+
+fn board() -> Board {
+    Board::from_cards(
+        Card(S::Clubs, R::Two),
+        Card(S::Hearts, R::Seven),
+        Card(S::Diamonds, R::Nine),
+        Card(S::Spades, R::Jack),
+        Card(S::Clubs, R::Four),
+    )
+}
+
+fn check_down(game: &mut Game) {
+    while !game.over {
+        let id = game.current_player().unwrap().id;
+        game.act(id, Action::Check).unwrap();
+    }
+}
+
+fn won(game: &Game) -> Vec<(u64, u32)> {
+    game.payouts.iter().map(|p| (p.id, p.amount)).collect()
+}
+
+fn limp(game: &mut Game) {
+    game.act(1, Action::Call).unwrap();
+    game.act(2, Action::Call).unwrap();
+    game.act(3, Action::Check).unwrap();
+}
+
+#[test]
+fn streets() {
+    let mut game = game();
+    assert_eq!(game.street, Street::Preflop);
+    assert_eq!(game.visible_board().len(), 0);
+    limp(&mut game);
+    for street in [Street::Flop, Street::Turn, Street::River].iter() {
+        assert_eq!(game.street, *street);
+        assert_eq!(game.current_player().unwrap().id, 2);
+        for id in [2, 3, 1].iter() {
+            game.act(*id, Action::Check).unwrap();
+        }
+    }
+    assert_eq!(game.visible_board().len(), 5);
+    assert_eq!(game.street, Street::Showdown);
+    assert!(game.over);
+    assert_eq!(game.act(1, Action::Check), Err(GameError::HandOver));
+}
+
+#[test]
+fn turn_order() {
+    let mut game = game();
+    assert_eq!(game.act(2, Action::Call), Err(GameError::NotYourTurn));
+    assert_eq!(game.act(4, Action::Call), Err(GameError::UnknownPlayer));
+    game.act(1, Action::Call).unwrap();
+    assert_eq!(game.current_player().unwrap().id, 2);
+}
+
+#[test]
+fn illegal_actions() {
+    let mut game = game();
+    assert_eq!(game.act(1, Action::Check), Err(GameError::CannotCheck { to_call: 10 }));
+    assert_eq!(game.act(1, Action::Bet(40)), Err(GameError::AlreadyBet));
+    assert_eq!(game.act(1, Action::Raise(15)), Err(GameError::RaiseTooSmall { min: 20 }));
+    assert_eq!(game.act(1, Action::Raise(2000)), Err(GameError::NotEnoughMoney { stack: 1000 }));
+    limp(&mut game);
+
+    assert_eq!(game.act(2, Action::Call), Err(GameError::NothingToCall));
+    assert_eq!(game.act(2, Action::Raise(50)), Err(GameError::NothingToRaise));
+    assert_eq!(game.act(2, Action::Bet(5)), Err(GameError::BetTooSmall { min: 10 }));
+    game.act(2, Action::Bet(40)).unwrap();
+    assert_eq!(game.act(3, Action::Raise(60)), Err(GameError::RaiseTooSmall { min: 80 }));
+    game.act(3, Action::Raise(80)).unwrap();
+    assert_eq!(game.min_raise, 40);
+    assert_eq!(game.act(1, Action::Raise(100)), Err(GameError::RaiseTooSmall { min: 120 }));
+}
+
+#[test]
+fn raise_reopens_action() {
+    let mut game = game();
+    game.act(1, Action::Raise(30)).unwrap();
+    game.act(2, Action::Call).unwrap();
+    game.act(3, Action::Raise(90)).unwrap();
+    assert_eq!(game.street, Street::Preflop);
+    game.act(1, Action::Call).unwrap();
+    assert_eq!(game.street, Street::Preflop);
+    game.act(2, Action::Call).unwrap();
+    assert_eq!(game.street, Street::Flop);
+    assert_eq!(game.pot(), 270);
+    assert_eq!(game.bet, 0);
+}
+
+#[test]
+fn everyone_folds() {
+    let mut game = game();
+    game.act(1, Action::Raise(100)).unwrap();
+    game.act(2, Action::Fold).unwrap();
+    game.act(3, Action::Fold).unwrap();
+    assert!(game.over);
+    assert_eq!(won(&game), vec![(1, 115)]);
+    assert_eq!(game.players.get_player(1).unwrap().money, 1015);
+}
+
+#[test]
+fn all_in_runs_out_the_board() {
+    let mut game = game();
+    rig(&mut game, &[
+        (1, Hand(Card(S::Spades, R::Ace), Card(S::Hearts, R::Ace))),
+        (2, Hand(Card(S::Spades, R::King), Card(S::Hearts, R::King))),
+        (3, Hand(Card(S::Spades, R::Three), Card(S::Hearts, R::Eight))),
+    ], board());
+    game.act(1, Action::AllIn).unwrap();
+    game.act(2, Action::Call).unwrap();
+    game.act(3, Action::Fold).unwrap();
+    assert_eq!(game.street, Street::Showdown);
+    assert_eq!(won(&game), vec![(1, 2010)]);
+    assert_eq!(game.players.get_player(1).unwrap().money, 2010);
+    assert_eq!(game.players.get_player(2).unwrap().money, 0);
+}
+
+#[test]
+fn split_pot() {
+    let mut game = game();
+    rig(&mut game, &[
+        (1, Hand(Card(S::Spades, R::Ace), Card(S::Hearts, R::King))),
+        (2, Hand(Card(S::Clubs, R::Ace), Card(S::Diamonds, R::King))),
+        (3, Hand(Card(S::Spades, R::Three), Card(S::Hearts, R::Eight))),
+    ], board());
+    game.act(1, Action::Raise(25)).unwrap();
+    game.act(2, Action::Call).unwrap();
+    game.act(3, Action::Call).unwrap();
+    game.act(2, Action::Bet(10)).unwrap();
+    game.act(3, Action::Fold).unwrap();
+    game.act(1, Action::Call).unwrap();
+    check_down(&mut game);
+    // The odd chip goes to the small blind, first left of the button.
+    assert_eq!(won(&game), vec![(2, 48), (1, 47)]);
+}
+
+#[test]
+fn short_all_in_side_pot() {
+    let mut game = Game::new(&[1, 2, 3], 1000, Blinds::new(5, 10));
+    game.players.get_player_mut(1).unwrap().money = 100;
+    game.new_hand().unwrap();
+    rig(&mut game, &[
+        (1, Hand(Card(S::Spades, R::Ace), Card(S::Hearts, R::Ace))),
+        (2, Hand(Card(S::Spades, R::King), Card(S::Hearts, R::King))),
+        (3, Hand(Card(S::Spades, R::Three), Card(S::Hearts, R::Eight))),
+    ], board());
+    game.act(1, Action::AllIn).unwrap();
+    game.act(2, Action::Raise(400)).unwrap();
+    game.act(3, Action::Call).unwrap();
+    assert_eq!(game.pots(), vec![
+        Pot { amount: 300, eligible: vec![1, 2, 3] },
+        Pot { amount: 600, eligible: vec![2, 3] },
+    ]);
+    check_down(&mut game);
+    assert_eq!(won(&game), vec![(1, 300), (2, 600)]);
+    assert_eq!(game.players.get_player(2).unwrap().money, 1200);
+}
+
+#[test]
+fn blinds_and_button() {
+    let mut game = game();
+    assert_eq!(game.players.button().id, 1);
+    assert_eq!(game.small_blind_player, Some(2));
+    assert_eq!(game.big_blind_player, Some(3));
+    assert_eq!(game.pot(), 15);
+    assert_eq!(game.to_call(1), 10);
+    assert_eq!(game.to_call(2), 5);
+    assert_eq!(game.current_player().unwrap().id, 1);
+
+    game.act(1, Action::Fold).unwrap();
+    game.act(2, Action::Fold).unwrap();
+    game.new_hand().unwrap();
+    assert_eq!(game.players.button().id, 2);
+    assert_eq!(game.small_blind_player, Some(3));
+    assert_eq!(game.big_blind_player, Some(1));
+    assert_eq!(game.current_player().unwrap().id, 2);
+}
+
+#[test]
+fn big_blind_option() {
+    let mut game = game();
+    game.act(1, Action::Call).unwrap();
+    game.act(2, Action::Call).unwrap();
+    assert_eq!(game.street, Street::Preflop);
+    assert_eq!(game.current_player().unwrap().id, 3);
+    game.act(3, Action::Raise(30)).unwrap();
+    assert_eq!(game.current_player().unwrap().id, 1);
+}
+
+#[test]
+fn heads_up() {
+    let mut game = Game::new(&[1, 2], 1000, Blinds::new(5, 10));
+    game.new_hand().unwrap();
+    assert_eq!(game.small_blind_player, Some(1));
+    assert_eq!(game.big_blind_player, Some(2));
+    // The button acts first before the flop and last after it.
+    assert_eq!(game.current_player().unwrap().id, 1);
+    game.act(1, Action::Call).unwrap();
+    game.act(2, Action::Check).unwrap();
+    assert_eq!(game.street, Street::Flop);
+    assert_eq!(game.current_player().unwrap().id, 2);
+}
+
+#[test]
+fn antes() {
+    let mut game = Game::new(&[1, 2, 3], 1000, Blinds::new(5, 10).with_ante(2));
+    game.new_hand().unwrap();
+    assert_eq!(game.pot(), 21);
+    assert_eq!(game.bet, 10);
+    assert_eq!(game.players.get_player(1).unwrap().money, 998);
+
+    let mut game = Game::new(&[1, 2, 3], 1000, Blinds::new(5, 10).with_big_blind_ante(10));
+    game.new_hand().unwrap();
+    assert_eq!(game.pot(), 25);
+    assert_eq!(game.players.get_player(1).unwrap().money, 1000);
+    assert_eq!(game.players.get_player(3).unwrap().money, 980);
+
+    // A big blind short of blind and ante posts the full blind first.
+    let mut game = Game::new(&[1, 2, 3], 1000, Blinds::new(5, 10).with_big_blind_ante(10));
+    game.players.get_player_mut(3).unwrap().money = 15;
+    game.new_hand().unwrap();
+    assert_eq!(game.pot(), 20);
+    assert_eq!(game.to_call(1), 10);
+    assert_eq!(game.players.get_player(3).unwrap().bet, 10);
+    assert_eq!(game.players.get_player(3).unwrap().money, 0);
+}
+
+#[test]
+fn busted_players_are_skipped() {
+    let mut game = Game::new(&[1, 2, 3], 1000, Blinds::new(5, 10));
+    game.players.get_player_mut(2).unwrap().money = 0;
+    game.new_hand().unwrap();
+    assert!(!game.players.get_player(2).unwrap().is_playing);
+    assert_eq!(game.small_blind_player, Some(1));
+    assert_eq!(game.big_blind_player, Some(3));
+}
+
+#[test]
+fn seeded_games_replay() {
+    let play = |seed| {
+        let mut game = Game::with_seed(&[1, 2, 3], 1000, Blinds::new(5, 10), seed);
+        let mut history = Vec::new();
+        for _ in 0..3 {
+            game.new_hand().unwrap();
+            let hands: Vec<Option<Hand>> = game.players.iter().map(|p| p.hand).collect();
+            let first = game.current_player().unwrap().id;
+            game.act(first, Action::Raise(30)).unwrap();
+            while !game.over {
+                let id = game.current_player().unwrap().id;
+                let action = if game.to_call(id) > 0 { Action::Call } else { Action::Check };
+                game.act(id, action).unwrap();
+            }
+            history.push((game.hand_seed, hands, game.board, won(&game)));
+        }
+        history
+    };
+    let history = play(99);
+    assert_eq!(history, play(99));
+    assert_ne!(history, play(100));
+
+    // A single hand can be dealt again from its seed.
+    let (seed, hands, board, _) = &history[1];
+    let mut game = Game::with_seed(&[1, 2, 3], 1000, Blinds::new(5, 10), 0);
+    game.new_hand().unwrap();
+    game.deck = Deck::from_seed(*seed);
+    let mut replayed = Vec::new();
+    for _ in 0..3 {
+        replayed.push(Hand::new(&mut game.deck));
+    }
+    assert_eq!(&replayed, hands);
+    assert_eq!(Board::new(&mut game.deck), *board);
+}
+
+#[test]
+fn pot_limit() {
+    let mut game = Game::new(&[1, 2, 3], 1000, Blinds::new(5, 10)).with_structure(Structure::PotLimit);
+    game.new_hand().unwrap();
+    assert_eq!(game.limits(1), Some(Limits { min: 20, max: 35 }));
+    assert_eq!(game.act(1, Action::Raise(40)), Err(GameError::RaiseTooBig { max: 35 }));
+    assert_eq!(game.act(1, Action::AllIn), Err(GameError::RaiseTooBig { max: 35 }));
+    game.act(1, Action::Raise(35)).unwrap();
+
+    // The small blind calls 30 into 50 and raises by 80.
+    assert_eq!(game.limits(2), Some(Limits { min: 60, max: 115 }));
+    game.act(2, Action::Call).unwrap();
+    game.act(3, Action::Call).unwrap();
+
+    assert_eq!(game.limits(2), Some(Limits { min: 10, max: 105 }));
+    assert_eq!(game.act(2, Action::Bet(106)), Err(GameError::BetTooBig { max: 105 }));
+    game.act(2, Action::Bet(105)).unwrap();
+}
+
+#[test]
+fn fixed_limit() {
+    let structure = Structure::FixedLimit { cap: 4 };
+    let mut game = Game::new(&[1, 2, 3], 1000, Blinds::new(5, 10)).with_structure(structure);
+    game.new_hand().unwrap();
+    assert_eq!(game.act(1, Action::Raise(30)), Err(GameError::RaiseTooBig { max: 20 }));
+    game.act(1, Action::Raise(20)).unwrap();
+    game.act(2, Action::Raise(30)).unwrap();
+    game.act(3, Action::Raise(40)).unwrap();
+
+    // The big blind and three raises make four bets, the cap.
+    assert_eq!(game.limits(1), None);
+    assert_eq!(game.act(1, Action::Raise(50)), Err(GameError::BettingCapped));
+    assert_eq!(game.act(1, Action::AllIn), Err(GameError::BettingCapped));
+    game.act(1, Action::Call).unwrap();
+    game.act(2, Action::Call).unwrap();
+
+    // Small bets on the flop, big bets on the turn and river.
+    assert_eq!(game.street, Street::Flop);
+    assert_eq!(game.act(2, Action::Bet(20)), Err(GameError::BetTooBig { max: 10 }));
+    check_down_to(&mut game, Street::Turn);
+    assert_eq!(game.limits(2), Some(Limits { min: 20, max: 20 }));
+    assert_eq!(game.act(2, Action::Bet(10)), Err(GameError::BetTooSmall { min: 20 }));
+    game.act(2, Action::Bet(20)).unwrap();
+    assert_eq!(game.limits(3), Some(Limits { min: 40, max: 40 }));
+}
+
+#[test]
+fn seat_between_hands() {
+    let mut game = game();
+    game.seat(4, 500).unwrap();
+    assert_eq!(game.seat(4, 500), Err(GameError::AlreadySeated));
+    assert_eq!(game.unseat(1).map(|p| p.id), Err(GameError::HandInProgress));
+    // Seated in the middle of the hand, 4 waits for the next one.
+    assert!(game.players.get_player(4).unwrap().hand.is_none());
+    game.act(1, Action::Fold).unwrap();
+    game.act(2, Action::Fold).unwrap();
+
+    assert_eq!(game.unseat(2).unwrap().money, 995);
+    assert_eq!(game.unseat(2).map(|p| p.id), Err(GameError::UnknownPlayer));
+
+    // The button moves on from player 1 to 3, 4 posts the small blind.
+    game.new_hand().unwrap();
+    assert_eq!(game.players.button().id, 3);
+    assert_eq!(game.small_blind_player, Some(4));
+    assert_eq!(game.big_blind_player, Some(1));
+    assert!(game.players.get_player(4).unwrap().hand.is_some());
+}
+
+#[test]
+fn seats_change_mid_hand() {
+    let mut game = Game::new(&[1, 2, 3, 4], 1000, Blinds::new(5, 10));
+    game.players.get_player_mut(3).unwrap().sitting_out = true;
+    game.new_hand().unwrap();
+    assert!(!game.players.get_player(3).unwrap().is_playing);
+    assert_eq!(game.big_blind_player, Some(4));
+    assert_eq!(game.current_player().unwrap().id, 1);
+
+    // Leaving before the player to act keeps the turn where it is.
+    assert_eq!(game.unseat(3).unwrap().money, 1000);
+    assert_eq!(game.current_player().unwrap().id, 1);
+    game.seat(5, 800).unwrap();
+    game.act(1, Action::Call).unwrap();
+    game.act(2, Action::Call).unwrap();
+    assert_eq!(game.current_player().unwrap().id, 4);
+    game.act(4, Action::Check).unwrap();
+    assert_eq!(game.street, Street::Flop);
+
+    // With everyone else sitting out, 5 has nobody to play against.
+    while !game.over {
+        let id = game.current_player().unwrap().id;
+        game.act(id, Action::Fold).unwrap();
+    }
+    for id in [1, 2, 4] {
+        game.players.get_player_mut(id).unwrap().sitting_out = true;
+    }
+    assert_eq!(game.new_hand(), Err(GameError::NotEnoughPlayers));
+}
+
+#[test]
+fn button_skips_sitting_out() {
+    // The seat after the button sits out: the button passes it, and heads-up
+    // the player sitting out doesn't post the small blind.
+    let mut game = Game::new(&[1, 2, 3], 1000, Blinds::new(5, 10));
+    game.new_hand().unwrap();
+    while !game.over {
+        let id = game.current_player().unwrap().id;
+        game.act(id, Action::Fold).unwrap();
+    }
+    let sitter = game.players.get_player_mut(2).unwrap();
+    sitter.sitting_out = true;
+    let money = sitter.money;
+    game.new_hand().unwrap();
+    assert_eq!(game.players.button().id, 3);
+    assert_eq!(game.small_blind_player, Some(3));
+    assert_eq!(game.big_blind_player, Some(1));
+    let sitter = game.players.get_player(2).unwrap();
+    assert!(!sitter.is_playing);
+    assert_eq!((sitter.money, sitter.committed), (money, 0));
+
+    // Nor does the first hand start with the button on them.
+    let mut game = Game::new(&[1, 2, 3], 1000, Blinds::new(5, 10));
+    game.players.get_player_mut(1).unwrap().sitting_out = true;
+    game.new_hand().unwrap();
+    assert_eq!(game.players.button().id, 2);
+    assert_eq!(game.small_blind_player, Some(2));
+    assert_eq!(game.big_blind_player, Some(3));
+}
+
+// Button 1, small blind 2, big blind 3 and 4 first to act, with the stacks given.
+fn four_handed(stacks: &[(u64, u32)]) -> Game {
+    let mut game = Game::new(&[1, 2, 3, 4], 1000, Blinds::new(5, 10));
+    for (id, money) in stacks {
+        game.players.get_player_mut(*id).unwrap().money = *money;
+    }
+    game.new_hand().unwrap();
+    game
+}
+
+#[test]
+fn short_all_in_does_not_reopen() {
+    let mut game = four_handed(&[(1, 45)]);
+    game.act(4, Action::Raise(30)).unwrap();
+    game.act(1, Action::AllIn).unwrap();
+    game.act(2, Action::Fold).unwrap();
+    // The big blind hadn't acted yet and may still raise.
+    assert_eq!(game.limits(3), Some(Limits { min: 65, max: 1000 }));
+    game.act(3, Action::Call).unwrap();
+
+    assert_eq!(game.limits(4), None);
+    assert_eq!(game.act(4, Action::Raise(100)), Err(GameError::NotReopened));
+    assert_eq!(game.act(4, Action::AllIn), Err(GameError::NotReopened));
+    game.act(4, Action::Call).unwrap();
+    assert_eq!(game.street, Street::Flop);
+}
+
+#[test]
+fn short_all_ins_add_up_to_a_full_raise() {
+    let mut game = four_handed(&[(1, 45), (2, 60)]);
+    game.act(4, Action::Raise(30)).unwrap();
+    game.act(1, Action::AllIn).unwrap();
+    game.act(2, Action::AllIn).unwrap();
+    game.act(3, Action::Fold).unwrap();
+
+    // 45 then 60 is 30 more than the 30 player 4 raised to, a full raise.
+    assert_eq!(game.limits(4), Some(Limits { min: 80, max: 1000 }));
+    game.act(4, Action::Raise(80)).unwrap();
+    assert!(game.over);
+}
+
+#[test]
+fn short_all_in_heads_up() {
+    let mut game = Game::new(&[1, 2], 1000, Blinds::new(5, 10));
+    game.players.get_player_mut(2).unwrap().money = 40;
+    game.new_hand().unwrap();
+    game.act(1, Action::Raise(30)).unwrap();
+    game.act(2, Action::AllIn).unwrap();
+
+    assert_eq!(game.act(1, Action::Raise(60)), Err(GameError::NotReopened));
+    game.act(1, Action::Call).unwrap();
+    assert!(game.over);
+    assert_eq!(game.visible_board().len(), 5);
+}
+
+#[test]
+fn all_in_blinds() {
+    // A big blind too short to post still has the others call the full blind.
+    let mut game = four_handed(&[(3, 6)]);
+    assert_eq!(game.to_call(4), 10);
+    game.act(4, Action::Call).unwrap();
+    game.act(1, Action::Call).unwrap();
+    game.act(2, Action::Call).unwrap();
+    assert_eq!(game.pots(), vec![
+        Pot { amount: 24, eligible: vec![1, 2, 3, 4] },
+        Pot { amount: 12, eligible: vec![1, 2, 4] },
+    ]);
+
+    // The big blind going all-in for less than a full raise doesn't let the
+    // limpers raise, only call or fold.
+    let mut game = four_handed(&[(3, 17)]);
+    game.act(4, Action::Call).unwrap();
+    game.act(1, Action::Call).unwrap();
+    game.act(2, Action::Call).unwrap();
+    game.act(3, Action::AllIn).unwrap();
+    assert_eq!(game.bet, 17);
+    assert_eq!(game.act(4, Action::Raise(40)), Err(GameError::NotReopened));
+    game.act(4, Action::Call).unwrap();
+    game.act(1, Action::Fold).unwrap();
+    game.act(2, Action::Call).unwrap();
+    assert_eq!(game.street, Street::Flop);
+}
+
+fn check_down_to(game: &mut Game, street: Street) {
+    while game.street != street {
+        let id = game.current_player().unwrap().id;
+        game.act(id, Action::Check).unwrap();
+    }
+}
@@ -1,16 +1,27 @@
 use std::collections::HashMap;
 use std::fmt;
-use std::cell::RefCell;
-use std::rc::Rc;
 
-use rand::seq::SliceRandom;
+use std::convert::TryFrom;
 
-mod combo;
+use rand::{rngs::StdRng, Rng, SeedableRng};
+use serde::{Deserialize, Serialize};
+
+pub mod combo;
+pub mod fair;
+pub mod ids;
+mod game;
+pub mod notation;
+pub mod pot;
+pub mod showdown;
+pub mod snapshot;
+pub mod structure;
+
+pub use game::{Action, Blinds, Game, GameError, Street};
 
 #[derive(PartialEq, Eq, Clone, Copy, Debug)]
 pub struct Card(Suit, Rank);
 
-#[derive(PartialEq, Eq, Hash, Clone, Copy, Debug)]
+#[derive(PartialEq, Eq, Hash, Clone, Copy, Debug, Serialize, Deserialize)]
 pub enum Suit {
     Clubs,
     Diamonds,
@@ -18,13 +29,20 @@ pub enum Suit {
     Spades
 }
 
+pub const SUITS: [Suit; 4] = [
+    Suit::Clubs,
+    Suit::Diamonds,
+    Suit::Hearts,
+    Suit::Spades
+];
+
 impl fmt::Display for Suit {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         write!(f, "{:?}", self)
     }
 }
 
-#[derive(PartialEq, Eq, PartialOrd, Ord, Clone, Copy, Debug)]
+#[derive(PartialEq, Eq, PartialOrd, Ord, Clone, Copy, Debug, Serialize, Deserialize)]
 pub enum Rank {
     Two,
     Three,
@@ -41,6 +59,22 @@ pub enum Rank {
     Ace
 }
 
+pub const RANKS: [Rank; 13] = [
+    Rank::Two,
+    Rank::Three,
+    Rank::Four,
+    Rank::Five,
+    Rank::Six,
+    Rank::Seven,
+    Rank::Eight,
+    Rank::Nine,
+    Rank::Ten,
+    Rank::Jack,
+    Rank::Queen,
+    Rank::King,
+    Rank::Ace
+];
+
 impl fmt::Display for Rank {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         write!(f, "{:?}", self)
@@ -48,7 +82,7 @@ impl fmt::Display for Rank {
 }
 
 impl Rank {
-    fn next(&self) -> Self {
+    pub fn next(&self) -> Self {
         match self {
             Rank::Two => Rank::Three,
             Rank::Three => Rank::Four,
@@ -66,11 +100,11 @@ impl Rank {
         }
     }
 
-    fn is_next(self, other: Self) -> bool {
+    pub fn is_next(self, other: Self) -> bool {
         self == other.next()
     }
 
-    fn prev(&self) -> Self {
+    pub fn prev(&self) -> Self {
         match self {
             Rank::Three => Rank::Two,
             Rank::Four => Rank::Three,
@@ -88,7 +122,7 @@ impl Rank {
         }
     }
 
-    fn is_prev(self, other: Self) -> bool {
+    pub fn is_prev(self, other: Self) -> bool {
         self == other.prev()
     }
 }
@@ -105,6 +139,19 @@ impl Card {
     pub fn rank(&self) -> Rank {
         self.1
     }
+
+    // Compact encoding of the card as a number from 0 to 51.
+    pub fn index(&self) -> u8 {
+        self.0 as u8 * 13 + self.1 as u8
+    }
+
+    pub fn from_index(i: u8) -> Option<Self> {
+        if i < 52 {
+            Some(Card(SUITS[(i / 13) as usize], RANKS[(i % 13) as usize]))
+        } else {
+            None
+        }
+    }
 }
 
 impl fmt::Display for Card {
@@ -113,12 +160,37 @@ impl fmt::Display for Card {
     }
 }
 
+#[derive(PartialEq, Eq, Clone, Debug, Serialize, Deserialize)]
 pub struct Deck {
     cards: Vec<Card>
 }
 
 impl Deck {
     pub fn new() -> Self {
+        Self::with_rng(&mut rand::thread_rng())
+    }
+
+    // The same seed always gives the same deck, so a hand can be replayed.
+    pub fn from_seed(seed: u64) -> Self {
+        Self::with_rng(&mut StdRng::seed_from_u64(seed))
+    }
+
+    pub fn with_rng<R: Rng + ?Sized>(rng: &mut R) -> Self {
+        let mut deck = Self::ordered();
+        deck.shuffle(rng);
+        deck
+    }
+
+    // Fisher-Yates shuffle, written out so the order only depends on the rng.
+    pub fn shuffle<R: Rng + ?Sized>(&mut self, rng: &mut R) {
+        for i in (1..self.cards.len()).rev() {
+            let j = rng.gen_range(0..=i);
+            self.cards.swap(i, j);
+        }
+    }
+
+    // A fresh deck, sorted by suit and rank.
+    pub fn ordered() -> Self {
         let mut cards = Vec::with_capacity(52);
             for s in [
                 Suit::Clubs,
@@ -147,44 +219,72 @@ impl Deck {
         Deck{ cards }
     }
 
+    // Deals the card on top of the deck.
     pub fn pop(&mut self) -> Option<Card> {
-        let res = self.cards
-                   .iter()
-                   .enumerate()
-                   .collect::<Vec<(usize, &Card)>>()
-                   .choose(&mut rand::thread_rng()).cloned();
-        if let Some((i, &out)) = res {
-            self.cards.remove(i);
-            return Some(out);
-        } else {
-            return None;
-        }
+        self.cards.pop()
     }
 
+    // Puts a card back on top of the deck.
     pub fn push(&mut self, card: Card) {
         self.cards.push(card);
     }
 
+    // Cards from the bottom of the deck to the top.
+    pub fn cards(&self) -> &[Card] {
+        &self.cards
+    }
+
     pub fn len(&self) -> usize {
         self.cards.len()
     }
+
+    pub fn is_empty(&self) -> bool {
+        self.cards.is_empty()
+    }
 }
 
-#[derive(PartialEq, Eq, Clone, Copy, Debug)]
+impl Default for Deck {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[derive(PartialEq, Eq, Clone, Copy, Debug, Serialize, Deserialize)]
 pub struct Hand (Card, Card);
 
 impl Hand {
     fn new(deck: &mut Deck) -> Option<Self> {
         Some(Hand(deck.pop()?, deck.pop()?))
     }
+
+    pub fn from_cards(c1: Card, c2: Card) -> Self {
+        Hand(c1, c2)
+    }
+
+    pub fn cards(&self) -> (Card, Card) {
+        (self.0, self.1)
+    }
 }
 
-#[derive(Clone, Copy, Debug)]
+#[derive(Clone, Copy, Debug, Serialize, Deserialize)]
 pub struct Player {
+    #[serde(with = "ids")]
     pub id: u64,
     pub hand: Option<Hand>,
     pub is_playing: bool,
     pub money: u32,
+    // Chips put in during the current betting round.
+    pub bet: u32,
+    // Chips put in during the whole hand, including `bet`.
+    pub committed: u32,
+    pub has_acted: bool,
+    // Highest bet of the round right after the player last acted in it, None
+    // before they act. Raising again takes a full raise over it.
+    #[serde(default)]
+    pub acted_at: Option<u32>,
+    // Not dealt in while set, keeping the seat and the chips.
+    #[serde(default)]
+    pub sitting_out: bool,
 }
 
 impl PartialEq for Player {
@@ -201,15 +301,45 @@ impl Player {
             id,
             hand: None,
             is_playing: false,
-            money
-       }
+            money,
+            bet: 0,
+            committed: 0,
+            has_acted: false,
+            acted_at: None,
+            sitting_out: false,
+        }
+    }
+
+    pub fn is_all_in(&self) -> bool {
+        self.is_playing && self.money == 0
+    }
+
+    // Whether the player still has decisions to make this hand.
+    pub fn can_act(&self) -> bool {
+        self.is_playing && self.money > 0
+    }
+
+    fn put_in(&mut self, amount: u32) {
+        self.money -= amount;
+        self.bet += amount;
+        self.committed += amount;
+    }
+
+    // Antes go to the pot without counting towards the player's bet.
+    fn post_ante(&mut self, ante: u32) {
+        let amount = ante.min(self.money);
+        self.money -= amount;
+        self.committed += amount;
     }
 }
 
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(into = "Seats", try_from = "Seats")]
 pub struct PlayerRing {
     vec: Vec<u64>,
     map: HashMap<u64, Player>,
     order_count: usize,
+    button: usize,
 }
 
 impl PlayerRing {
@@ -222,21 +352,170 @@ impl PlayerRing {
         PlayerRing {
             vec,
             map,
-            order_count: 0
+            order_count: 0,
+            button: 0,
         }
     }
 
+    #[allow(clippy::should_implement_trait)]
     pub fn next(&mut self) -> &Player {
         let return_player = self.vec[self.order_count];
         self.order_count = (self.order_count + 1) % self.vec.len(); 
         &self.map[&return_player]
     }
 
-    pub fn get_player(&mut self, id: u64) -> Option<&Player> {
+    pub fn get_player(&self, id: u64) -> Option<&Player> {
         self.map.get(&id)
     }
+
+    pub fn get_player_mut(&mut self, id: u64) -> Option<&mut Player> {
+        self.map.get_mut(&id)
+    }
+
+    // The player whose turn it is, that is, the one `next` would return.
+    pub fn current(&self) -> &Player {
+        &self.map[&self.vec[self.order_count]]
+    }
+
+    pub fn set_current(&mut self, id: u64) -> bool {
+        if let Some(i) = self.vec.iter().position(|p| *p == id) {
+            self.order_count = i;
+            true
+        } else {
+            false
+        }
+    }
+
+    // Moves the turn to the next player after the current one that can still act.
+    pub fn advance(&mut self) -> Option<&Player> {
+        for step in 1..=self.vec.len() {
+            let i = (self.order_count + step) % self.vec.len();
+            if self.map[&self.vec[i]].can_act() {
+                self.order_count = i;
+                return Some(&self.map[&self.vec[i]]);
+            }
+        }
+        None
+    }
+
+    pub fn button(&self) -> &Player {
+        &self.map[&self.vec[self.button]]
+    }
+
+    // Moves the button to the next player that still has money and isn't
+    // sitting out.
+    pub fn move_button(&mut self) {
+        let id = self.vec[self.button];
+        if let Some(i) = self.next_after(id, |p| p.money > 0 && !p.sitting_out)
+                .and_then(|p| self.vec.iter().position(|id| *id == p.id)) {
+            self.button = i;
+        }
+    }
+
+    // First player after the given one in seat order that satisfies `f`,
+    // wrapping around the table and ending with the player itself.
+    pub fn next_after<F: Fn(&Player) -> bool>(&self, id: u64, f: F) -> Option<&Player> {
+        if !self.map.contains_key(&id) {
+            return None;
+        }
+        self.iter_after(id).find(|p| f(p))
+    }
+
+    // Players in seat order starting left of the given one and ending with it.
+    pub fn iter_after(&self, id: u64) -> impl Iterator<Item = &Player> {
+        let start = self.vec.iter().position(|p| *p == id).unwrap_or(0);
+        (1..=self.vec.len()).map(move |step| &self.map[&self.vec[(start + step) % self.vec.len()]])
+    }
+
+    pub fn len(&self) -> usize {
+        self.vec.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.vec.is_empty()
+    }
+
+    // Seats a player last in seat order, right before the first seat.
+    pub fn add(&mut self, id: u64, money: u32) -> bool {
+        if self.map.contains_key(&id) {
+            return false;
+        }
+        self.vec.push(id);
+        self.map.insert(id, Player::new(id, money));
+        true
+    }
+
+    // Frees a player's seat, also in the middle of a hand. The button and the
+    // turn of a player leaving stay on the seat before theirs, so they still
+    // move on to the player after.
+    pub fn remove(&mut self, id: u64) -> Option<Player> {
+        let i = self.vec.iter().position(|p| *p == id)?;
+        self.vec.remove(i);
+        let len = self.vec.len().max(1);
+        for seat in [&mut self.button, &mut self.order_count] {
+            if *seat > i {
+                *seat -= 1;
+            } else if *seat == i {
+                *seat = (i + len - 1) % len;
+            }
+        }
+        self.map.remove(&id)
+    }
+
+    // Players in seat order.
+    pub fn iter(&self) -> impl Iterator<Item = &Player> {
+        self.vec.iter().map(move |id| &self.map[id])
+    }
+
+    pub fn iter_mut(&mut self) -> impl Iterator<Item = &mut Player> {
+        let vec = &self.vec;
+        let mut players: Vec<&mut Player> = self.map.values_mut().collect();
+        players.sort_by_key(|p| vec.iter().position(|id| *id == p.id));
+        players.into_iter()
+    }
 }
 
+// How a PlayerRing is stored: its players in seat order along with the
+// seats of the player to act and of the button.
+#[derive(Serialize, Deserialize)]
+struct Seats {
+    seats: Vec<Player>,
+    turn: usize,
+    button: usize,
+}
+
+impl From<PlayerRing> for Seats {
+    fn from(ring: PlayerRing) -> Self {
+        Seats {
+            seats: ring.iter().cloned().collect(),
+            turn: ring.order_count,
+            button: ring.button,
+        }
+    }
+}
+
+impl TryFrom<Seats> for PlayerRing {
+    type Error = String;
+
+    fn try_from(seats: Seats) -> Result<Self, Self::Error> {
+        let vec: Vec<u64> = seats.seats.iter().map(|p| p.id).collect();
+        let map: HashMap<u64, Player> = seats.seats.into_iter().map(|p| (p.id, p)).collect();
+        if map.len() != vec.len() {
+            return Err(String::from("a player is seated twice"));
+        }
+        if !vec.is_empty() && (seats.turn >= vec.len() || seats.button >= vec.len()) {
+            return Err(String::from("turn and button must point to a seat"));
+        }
+        Ok(PlayerRing {
+            vec,
+            map,
+            order_count: seats.turn,
+            button: seats.button,
+        })
+    }
+}
+
+#[derive(PartialEq, Eq, Clone, Copy, Debug, Serialize, Deserialize)]
 pub struct Board(Card, Card, Card, Card, Card);
 
 
@@ -245,7 +524,7 @@ impl Board {
        Some(Board(deck.pop()?, deck.pop()?, deck.pop()?, deck.pop()?, deck.pop()?))
     }
 
-    fn from_cards(c1: Card, c2: Card, c3: Card, c4: Card, c5: Card) -> Self {
+    pub fn from_cards(c1: Card, c2: Card, c3: Card, c4: Card, c5: Card) -> Self {
         Board(c1, c2, c3, c4, c5)
     }
 
@@ -260,23 +539,9 @@ impl Board {
     pub fn river (&self) -> Card {
         self.4
     }
-}
-
-pub struct Game {
-    pub deck: Deck,
-    pub board: Option<Board>,
-    pub players: HashMap<u64, Option<Hand>>,
-    pub bet: u16,
-}
 
-impl Game {
-    pub fn new(players: Vec<u64>) -> Self {
-        Game {
-            deck: Deck::new(),
-            board: None,
-            players: players.into_iter().map(|u| { (u, None) }).collect(),
-            bet: 0,
-        }
+    pub fn cards(&self) -> [Card; 5] {
+        [self.0, self.1, self.2, self.3, self.4]
     }
 }
 
@@ -0,0 +1,211 @@
+// Standard short card notation: a rank out of "23456789TJQKA" followed by a
+// suit out of "cdhs", like "As" or "Td". Hands and boards are written as their
+// cards one after the other, like "AhKh" or "Qs7c2d".
+use std::fmt;
+use std::str::FromStr;
+
+use serde::{de::Error, Deserialize, Deserializer, Serialize, Serializer};
+
+use super::*;
+
+#[cfg(test)]
+mod test;
+
+#[derive(PartialEq, Eq, Clone, Debug)]
+pub enum ParseCardError {
+    Empty,
+    InvalidRank(char),
+    InvalidSuit(char),
+    MissingSuit(char),
+    WrongCount { expected: usize, found: usize },
+    Duplicate(Card),
+}
+
+impl fmt::Display for ParseCardError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ParseCardError::Empty =>
+                write!(f, "no cards given"),
+            ParseCardError::InvalidRank(c) =>
+                write!(f, "'{}' is not a rank, expected one of 23456789TJQKA", c),
+            ParseCardError::InvalidSuit(c) =>
+                write!(f, "'{}' is not a suit, expected one of c, d, h or s", c),
+            ParseCardError::MissingSuit(r) =>
+                write!(f, "the rank '{}' has no suit after it", r),
+            ParseCardError::WrongCount { expected, found } =>
+                write!(f, "expected {} cards but found {}", expected, found),
+            ParseCardError::Duplicate(card) =>
+                write!(f, "{} appears more than once", card.short()),
+        }
+    }
+}
+
+impl std::error::Error for ParseCardError {}
+
+impl Rank {
+    pub fn short(&self) -> char {
+        match self {
+            Rank::Two => '2',
+            Rank::Three => '3',
+            Rank::Four => '4',
+            Rank::Five => '5',
+            Rank::Six => '6',
+            Rank::Seven => '7',
+            Rank::Eight => '8',
+            Rank::Nine => '9',
+            Rank::Ten => 'T',
+            Rank::Jack => 'J',
+            Rank::Queen => 'Q',
+            Rank::King => 'K',
+            Rank::Ace => 'A'
+        }
+    }
+
+    fn from_char(c: char) -> Result<Self, ParseCardError> {
+        RANKS
+            .iter()
+            .find(|r| r.short() == c.to_ascii_uppercase())
+            .cloned()
+            .ok_or(ParseCardError::InvalidRank(c))
+    }
+}
+
+impl Suit {
+    pub fn short(&self) -> char {
+        match self {
+            Suit::Clubs => 'c',
+            Suit::Diamonds => 'd',
+            Suit::Hearts => 'h',
+            Suit::Spades => 's'
+        }
+    }
+
+    pub fn symbol(&self) -> char {
+        match self {
+            Suit::Clubs => '♣',
+            Suit::Diamonds => '♦',
+            Suit::Hearts => '♥',
+            Suit::Spades => '♠'
+        }
+    }
+
+    fn from_char(c: char) -> Result<Self, ParseCardError> {
+        SUITS
+            .iter()
+            .find(|s| s.short() == c.to_ascii_lowercase() || s.symbol() == c)
+            .cloned()
+            .ok_or(ParseCardError::InvalidSuit(c))
+    }
+}
+
+impl Card {
+    pub fn short(&self) -> String {
+        format!("{}{}", self.1.short(), self.0.short())
+    }
+}
+
+impl Hand {
+    pub fn short(&self) -> String {
+        format!("{}{}", self.0.short(), self.1.short())
+    }
+}
+
+impl Board {
+    pub fn short(&self) -> String {
+        self.cards().iter().map(|c| c.short()).collect()
+    }
+}
+
+// Parses any number of distinct cards, allowing spaces and commas between them.
+pub fn parse_cards(s: &str) -> Result<Vec<Card>, ParseCardError> {
+    let mut chars = s.chars().filter(|c| !c.is_whitespace() && *c != ',');
+    let mut cards: Vec<Card> = Vec::new();
+    while let Some(r) = chars.next() {
+        let rank = Rank::from_char(r)?;
+        let suit = Suit::from_char(chars.next().ok_or(ParseCardError::MissingSuit(r))?)?;
+        let card = Card(suit, rank);
+        if cards.contains(&card) {
+            return Err(ParseCardError::Duplicate(card));
+        }
+        cards.push(card);
+    }
+    if cards.is_empty() {
+        return Err(ParseCardError::Empty);
+    }
+    Ok(cards)
+}
+
+fn parse_exactly(s: &str, expected: usize) -> Result<Vec<Card>, ParseCardError> {
+    let cards = parse_cards(s)?;
+    if cards.len() != expected {
+        return Err(ParseCardError::WrongCount { expected, found: cards.len() });
+    }
+    Ok(cards)
+}
+
+impl FromStr for Rank {
+    type Err = ParseCardError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut chars = s.trim().chars();
+        match (chars.next(), chars.next()) {
+            (Some(c), None) => Rank::from_char(c),
+            (None, _) => Err(ParseCardError::Empty),
+            (Some(c), Some(_)) => Err(ParseCardError::InvalidRank(c)),
+        }
+    }
+}
+
+impl FromStr for Suit {
+    type Err = ParseCardError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut chars = s.trim().chars();
+        match (chars.next(), chars.next()) {
+            (Some(c), None) => Suit::from_char(c),
+            (None, _) => Err(ParseCardError::Empty),
+            (Some(c), Some(_)) => Err(ParseCardError::InvalidSuit(c)),
+        }
+    }
+}
+
+impl FromStr for Card {
+    type Err = ParseCardError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ok(parse_exactly(s, 1)?[0])
+    }
+}
+
+impl FromStr for Hand {
+    type Err = ParseCardError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let cards = parse_exactly(s, 2)?;
+        Ok(Hand(cards[0], cards[1]))
+    }
+}
+
+impl FromStr for Board {
+    type Err = ParseCardError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let c = parse_exactly(s, 5)?;
+        Ok(Board(c[0], c[1], c[2], c[3], c[4]))
+    }
+}
+
+// Cards are stored in short notation, like "As".
+impl Serialize for Card {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&self.short())
+    }
+}
+
+impl<'de> Deserialize<'de> for Card {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        String::deserialize(deserializer)?
+            .parse()
+            .map_err(D::Error::custom)
+    }
+}
@@ -0,0 +1,87 @@
+use super::*;
+
+type R = Rank;
+type S = Suit;
+
+#[test]
+fn ranks_and_suits() {
+    for r in RANKS.iter() {
+        assert_eq!(r.short().to_string().parse::<Rank>(), Ok(*r));
+    }
+    for s in SUITS.iter() {
+        assert_eq!(s.short().to_string().parse::<Suit>(), Ok(*s));
+    }
+    assert_eq!("t".parse::<Rank>(), Ok(R::Ten));
+    assert_eq!("♥".parse::<Suit>(), Ok(S::Hearts));
+    assert_eq!("1".parse::<Rank>(), Err(ParseCardError::InvalidRank('1')));
+    assert_eq!("10".parse::<Rank>(), Err(ParseCardError::InvalidRank('1')));
+    assert_eq!("x".parse::<Suit>(), Err(ParseCardError::InvalidSuit('x')));
+    assert_eq!("".parse::<Suit>(), Err(ParseCardError::Empty));
+}
+
+#[test]
+fn cards() {
+    assert_eq!("As".parse::<Card>(), Ok(Card(S::Spades, R::Ace)));
+    assert_eq!("Td".parse::<Card>(), Ok(Card(S::Diamonds, R::Ten)));
+    assert_eq!(" 7C ".parse::<Card>(), Ok(Card(S::Clubs, R::Seven)));
+    assert_eq!(Card(S::Hearts, R::Jack).short(), "Jh");
+    for i in 0..52 {
+        let card = Card::from_index(i).unwrap();
+        assert_eq!(card.short().parse::<Card>(), Ok(card));
+    }
+
+    assert_eq!("Ax".parse::<Card>(), Err(ParseCardError::InvalidSuit('x')));
+    assert_eq!("Zs".parse::<Card>(), Err(ParseCardError::InvalidRank('Z')));
+    assert_eq!("A".parse::<Card>(), Err(ParseCardError::MissingSuit('A')));
+    assert_eq!(
+        "AsKs".parse::<Card>(),
+        Err(ParseCardError::WrongCount { expected: 1, found: 2 })
+    );
+}
+
+#[test]
+fn hands() {
+    let hand: Hand = "AhKh".parse().unwrap();
+    assert_eq!(hand, Hand(Card(S::Hearts, R::Ace), Card(S::Hearts, R::King)));
+    assert_eq!(hand.short(), "AhKh");
+    assert_eq!("Ah Kh".parse::<Hand>(), Ok(hand));
+    assert_eq!(
+        "AhAh".parse::<Hand>(),
+        Err(ParseCardError::Duplicate(Card(S::Hearts, R::Ace)))
+    );
+    assert_eq!(
+        "Ah".parse::<Hand>(),
+        Err(ParseCardError::WrongCount { expected: 2, found: 1 })
+    );
+}
+
+#[test]
+fn boards() {
+    let board: Board = "Qs7c2dJhTh".parse().unwrap();
+    assert_eq!(board.flop(), (
+        Card(S::Spades, R::Queen),
+        Card(S::Clubs, R::Seven),
+        Card(S::Diamonds, R::Two)
+    ));
+    assert_eq!(board.river(), Card(S::Hearts, R::Ten));
+    assert_eq!(board.short(), "Qs7c2dJhTh");
+
+    // A flop alone is a list of cards, not a full board.
+    assert_eq!(parse_cards("Qs7c2d").unwrap().len(), 3);
+    assert_eq!(
+        "Qs7c2d".parse::<Board>(),
+        Err(ParseCardError::WrongCount { expected: 5, found: 3 })
+    );
+}
+
+#[test]
+fn error_messages() {
+    assert_eq!(
+        "Ax".parse::<Card>().unwrap_err().to_string(),
+        "'x' is not a suit, expected one of c, d, h or s"
+    );
+    assert_eq!(
+        "AsAs".parse::<Hand>().unwrap_err().to_string(),
+        "As appears more than once"
+    );
+}
@@ -0,0 +1,76 @@
+#[cfg(test)]
+mod test;
+
+use serde::{Deserialize, Serialize};
+
+use super::ids;
+
+#[derive(PartialEq, Eq, Clone, Debug, Serialize, Deserialize)]
+pub struct Pot {
+    pub amount: u32,
+    // Players that can win this pot, in seat order.
+    #[serde(with = "ids::list")]
+    pub eligible: Vec<u64>,
+}
+
+#[derive(PartialEq, Eq, Clone, Copy, Debug)]
+pub struct Contribution {
+    pub id: u64,
+    pub amount: u32,
+    pub in_hand: bool,
+}
+
+// Builds the main pot followed by the side pots, a new one for every all-in
+// amount of a player still in the hand. Folded chips go to the pots they reach.
+pub fn build_pots(contributions: &[Contribution]) -> Vec<Pot> {
+    let mut levels: Vec<u32> = contributions
+        .iter()
+        .filter(|c| c.in_hand)
+        .map(|c| c.amount)
+        .collect();
+    levels.sort_unstable();
+    levels.dedup();
+
+    let mut pots: Vec<Pot> = Vec::new();
+    let mut previous = 0;
+    for level in levels {
+        let amount = contributions
+            .iter()
+            .map(|c| c.amount.min(level) - c.amount.min(previous))
+            .sum();
+        let eligible = contributions
+            .iter()
+            .filter(|c| c.in_hand && c.amount >= level)
+            .map(|c| c.id)
+            .collect();
+        pots.push(Pot { amount, eligible });
+        previous = level;
+    }
+
+    let leftover: u32 = contributions
+        .iter()
+        .map(|c| c.amount.saturating_sub(previous))
+        .sum();
+    if let Some(last) = pots.last_mut() {
+        last.amount += leftover;
+    }
+    pots.retain(|p| p.amount > 0);
+    pots
+}
+
+impl Pot {
+    // Shares the pot between the winners, handing the odd chips one by one
+    // to the first winners in the given order.
+    pub fn split(&self, winners: &[u64]) -> Vec<(u64, u32)> {
+        if winners.is_empty() {
+            return Vec::new();
+        }
+        let share = self.amount / winners.len() as u32;
+        let odd_chips = (self.amount % winners.len() as u32) as usize;
+        winners
+            .iter()
+            .enumerate()
+            .map(|(i, id)| (*id, share + if i < odd_chips { 1 } else { 0 }))
+            .collect()
+    }
+}
@@ -0,0 +1,46 @@
+use super::*;
+
+fn c(id: u64, amount: u32, in_hand: bool) -> Contribution {
+    Contribution { id, amount, in_hand }
+}
+
+#[test]
+fn single_pot() {
+    let pots = build_pots(&[c(1, 100, true), c(2, 100, true), c(3, 40, false)]);
+    assert_eq!(pots, vec![Pot { amount: 240, eligible: vec![1, 2] }]);
+}
+
+#[test]
+fn side_pots() {
+    let pots = build_pots(&[
+        c(1, 50, true),
+        c(2, 300, true),
+        c(3, 300, true),
+        c(4, 120, true),
+        c(5, 80, false),
+    ]);
+    assert_eq!(pots, vec![
+        Pot { amount: 250, eligible: vec![1, 2, 3, 4] },
+        Pot { amount: 240, eligible: vec![2, 3, 4] },
+        Pot { amount: 360, eligible: vec![2, 3] },
+    ]);
+}
+
+#[test]
+fn uncalled_bet() {
+    let pots = build_pots(&[c(1, 100, true), c(2, 500, true)]);
+    assert_eq!(pots, vec![
+        Pot { amount: 200, eligible: vec![1, 2] },
+        Pot { amount: 400, eligible: vec![2] },
+    ]);
+}
+
+#[test]
+fn split() {
+    let pot = Pot { amount: 100, eligible: vec![1, 2, 3] };
+    assert_eq!(pot.split(&[2]), vec![(2, 100)]);
+    assert_eq!(pot.split(&[3, 1, 2]), vec![(3, 34), (1, 33), (2, 33)]);
+    let pot = Pot { amount: 101, eligible: vec![1, 2, 3] };
+    assert_eq!(pot.split(&[3, 1, 2]), vec![(3, 34), (1, 34), (2, 33)]);
+    assert_eq!(pot.split(&[]), vec![]);
+}
@@ -0,0 +1,90 @@
+use serde::{Deserialize, Serialize};
+
+use super::*;
+use super::combo::{find_best_hand, BestHand};
+use super::pot::Pot;
+
+#[cfg(test)]
+mod test;
+
+#[derive(PartialEq, Eq, Clone, Copy, Debug, Serialize, Deserialize)]
+pub struct Payout {
+    // Index of the pot in the list, 0 being the main pot.
+    pub pot: usize,
+    #[serde(with = "ids")]
+    pub id: u64,
+    pub amount: u32,
+    // Hand the pot was won with, None when everyone else folded.
+    pub hand: Option<BestHand>,
+}
+
+pub fn best_hand(hand: Hand, board: Board) -> BestHand {
+    let (c1, c2) = hand.cards();
+    let mut cards = board.cards().to_vec();
+    cards.push(c1);
+    cards.push(c2);
+    find_best_hand(&cards)
+}
+
+// Best hand of every player still in the hand, starting left of the button.
+// Nothing is shown down when only one player is left.
+pub fn rank_hands(players: &PlayerRing, board: Option<Board>) -> Vec<(u64, BestHand)> {
+    let board = match board {
+        Some(board) => board,
+        None => return Vec::new(),
+    };
+    let button = players.button().id;
+    let contenders: Vec<&Player> = players
+        .iter_after(button)
+        .filter(|p| p.is_playing)
+        .collect();
+    if contenders.len() < 2 {
+        return Vec::new();
+    }
+    contenders
+        .iter()
+        .filter_map(|p| p.hand.map(|h| (p.id, best_hand(h, board))))
+        .collect()
+}
+
+// Splits every pot between the best hands among its eligible players. The
+// odd chips of a split pot go to the winners closest to the left of the button.
+pub fn resolve(players: &PlayerRing, board: Option<Board>, pots: &[Pot]) -> Vec<Payout> {
+    let hands = rank_hands(players, board);
+    let button = players.button().id;
+    let mut payouts = Vec::new();
+
+    for (i, pot) in pots.iter().enumerate() {
+        let contenders: Vec<&(u64, BestHand)> = hands
+            .iter()
+            .filter(|(id, _)| pot.eligible.contains(id))
+            .collect();
+        let best = contenders.iter().map(|(_, h)| h.combo).max();
+        let winners: Vec<u64> = match best {
+            Some(best) => contenders
+                .iter()
+                .filter(|(_, h)| h.combo == best)
+                .map(|(id, _)| *id)
+                .collect(),
+            // Nobody showed down for this pot, so it goes to whoever is left.
+            None => players
+                .iter_after(button)
+                .map(|p| p.id)
+                .filter(|id| pot.eligible.contains(id))
+                .take(1)
+                .collect(),
+        };
+
+        payouts.extend(
+            pot.split(&winners)
+                .into_iter()
+                .map(|(id, amount)| Payout {
+                    pot: i,
+                    id,
+                    amount,
+                    hand: contenders.iter().find(|(c, _)| *c == id).map(|(_, h)| *h),
+                })
+        );
+    }
+    payouts
+}
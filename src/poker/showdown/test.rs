@@ -0,0 +1,104 @@
+use super::*;
+use super::super::combo::CardCombo;
+use super::super::pot::{build_pots, Contribution};
+
+type R = Rank;
+type S = Suit;
+
+fn board() -> Board {
+    Board::from_cards(
+        Card(S::Clubs, R::Two),
+        Card(S::Hearts, R::Seven),
+        Card(S::Diamonds, R::Nine),
+        Card(S::Spades, R::Jack),
+        Card(S::Clubs, R::Four),
+    )
+}
+
+// Seats players 1 to 4 with the button on player 1.
+fn ring(seats: &[(u64, Option<Hand>, u32)]) -> (PlayerRing, Vec<Pot>) {
+    let ids: Vec<u64> = seats.iter().map(|(id, _, _)| *id).collect();
+    let mut ring = PlayerRing::new(&ids, 0);
+    let mut contributions = Vec::new();
+    for (id, hand, committed) in seats {
+        let p = ring.get_player_mut(*id).unwrap();
+        p.hand = *hand;
+        p.is_playing = hand.is_some();
+        p.committed = *committed;
+        contributions.push(Contribution { id: *id, amount: *committed, in_hand: p.is_playing });
+    }
+    (ring, build_pots(&contributions))
+}
+
+fn hand(c1: Card, c2: Card) -> Option<Hand> {
+    Some(Hand(c1, c2))
+}
+
+#[test]
+fn single_winner() {
+    let (ring, pots) = ring(&[
+        (1, hand(Card(S::Spades, R::Ace), Card(S::Hearts, R::Ace)), 100),
+        (2, hand(Card(S::Spades, R::King), Card(S::Hearts, R::King)), 100),
+        (3, None, 20),
+    ]);
+    let payouts = resolve(&ring, Some(board()), &pots);
+    assert_eq!(payouts.len(), 1);
+    assert_eq!((payouts[0].pot, payouts[0].id, payouts[0].amount), (0, 1, 220));
+    let hand = payouts[0].hand.unwrap();
+    assert_eq!(hand.combo, CardCombo::Pair(R::Ace, R::Jack, R::Nine, R::Seven));
+    assert_eq!(hand.cards, [
+        Card(S::Spades, R::Ace),
+        Card(S::Hearts, R::Ace),
+        Card(S::Spades, R::Jack),
+        Card(S::Diamonds, R::Nine),
+        Card(S::Hearts, R::Seven),
+    ]);
+}
+
+#[test]
+fn everyone_else_folded() {
+    let (ring, pots) = ring(&[
+        (1, None, 30),
+        (2, hand(Card(S::Spades, R::King), Card(S::Hearts, R::King)), 60),
+        (3, None, 60),
+    ]);
+    assert_eq!(rank_hands(&ring, Some(board())), vec![]);
+    assert_eq!(resolve(&ring, Some(board()), &pots), vec![
+        Payout { pot: 0, id: 2, amount: 150, hand: None },
+    ]);
+}
+
+#[test]
+fn odd_chip_left_of_button() {
+    let (ring, pots) = ring(&[
+        (1, hand(Card(S::Spades, R::Ace), Card(S::Hearts, R::King)), 50),
+        (2, hand(Card(S::Spades, R::Queen), Card(S::Hearts, R::Eight)), 50),
+        (3, hand(Card(S::Clubs, R::Ace), Card(S::Diamonds, R::King)), 50),
+        (4, None, 1),
+    ]);
+    let won: Vec<(u64, u32)> = resolve(&ring, Some(board()), &pots)
+        .iter()
+        .map(|p| (p.id, p.amount))
+        .collect();
+    assert_eq!(won, vec![(3, 76), (1, 75)]);
+}
+
+#[test]
+fn split_side_pot() {
+    let (ring, pots) = ring(&[
+        (1, hand(Card(S::Spades, R::Eight), Card(S::Hearts, R::Ten)), 50),
+        (2, hand(Card(S::Spades, R::Ace), Card(S::Hearts, R::King)), 200),
+        (3, hand(Card(S::Clubs, R::Ace), Card(S::Diamonds, R::King)), 201),
+    ]);
+    let payouts: Vec<_> = resolve(&ring, Some(board()), &pots)
+        .iter()
+        .map(|p| (p.pot, p.id, p.amount, p.hand.map(|h| h.combo)))
+        .collect();
+    let ak = CardCombo::HighCard(R::Ace, R::King, R::Jack, R::Nine, R::Seven);
+    assert_eq!(payouts, vec![
+        (0, 1, 150, Some(CardCombo::Straight(R::Jack))),
+        (1, 2, 150, Some(ak)),
+        (1, 3, 150, Some(ak)),
+        (2, 3, 1, Some(ak)),
+    ]);
+}
@@ -0,0 +1,151 @@
+// Versioned JSON snapshots of a whole table.
+//
+// Schema version 4, every field always present:
+//
+//     {
+//       "version": 4,
+//       "visible_board": [card],                  cards face up on the table
+//       "game": {
+//         "seed": string, "hand_seed": string,   u64 as decimal strings
+//         "deck": { "cards": [card] },            bottom of the deck first
+//         "board": [card; 5] | null,              dealt with the hand
+//         "players": {
+//           "seats": [{
+//             "id": string,                       Discord user id
+//             "hand": [card; 2] | null,
+//             "is_playing": bool,                 still in the hand
+//             "money": u32, "bet": u32, "committed": u32, "has_acted": bool,
+//             "acted_at": u32 | null,             bet the player last acted on
+//             "sitting_out": bool                 not dealt in
+//           }],
+//           "turn": usize, "button": usize        indices into "seats"
+//         },
+//         "bet": u32, "min_raise": u32,
+//         "raises": u32,                          bets and full raises this round
+//         "blinds": { "small": u32, "big": u32, "ante": u32, "big_blind_ante": bool },
+//         "structure": { "kind": "no-limit" | "pot-limit" }
+//                    | { "kind": "fixed-limit", "cap": u32 },
+//         "small_blind_player": string | null, "big_blind_player": string | null,
+//         "hand_number": u32,
+//         "street": "Preflop" | "Flop" | "Turn" | "River" | "Showdown",
+//         "over": bool,
+//         "payouts": [{
+//           "pot": usize, "id": string, "amount": u32,
+//           "hand": { "combo": combo, "cards": [card; 5] } | null
+//         }]
+//       }
+//     }
+//
+// A card is its short notation, like "As" or "Td". A combo is an object with
+// the name of the CardCombo variant as key and its ranks as value, like
+// { "Pair": ["Ace", "King", "Nine", "Four"] }, except "RoyalFlush" which is a
+// plain string.
+//
+// Any change to this layout has to bump SCHEMA_VERSION.
+//
+// Version 1 had no "raises" nor "structure", its games read as no-limit, and
+// versions 1 and 2 had no "acted_at", read as null. Before version 4 nobody
+// sat out, "sitting_out" reads as false.
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+
+use super::*;
+
+#[cfg(test)]
+mod test;
+
+pub const SCHEMA_VERSION: u32 = 4;
+// Oldest version that can still be read
+pub const MIN_SCHEMA_VERSION: u32 = 1;
+
+#[derive(Debug)]
+pub enum SnapshotError {
+    Json(serde_json::Error),
+    UnsupportedVersion(u32),
+}
+
+impl fmt::Display for SnapshotError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            SnapshotError::Json(why) =>
+                write!(f, "invalid snapshot: {}", why),
+            SnapshotError::UnsupportedVersion(v) =>
+                write!(f, "snapshot version {} is not supported, expected {} to {}",
+                    v, MIN_SCHEMA_VERSION, SCHEMA_VERSION),
+        }
+    }
+}
+
+impl std::error::Error for SnapshotError {}
+
+impl From<serde_json::Error> for SnapshotError {
+    fn from(why: serde_json::Error) -> Self {
+        SnapshotError::Json(why)
+    }
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Snapshot {
+    pub version: u32,
+    pub visible_board: Vec<Card>,
+    pub game: Game,
+}
+
+#[derive(Deserialize)]
+struct Version {
+    version: u32,
+}
+
+impl Snapshot {
+    pub fn new(game: &Game) -> Self {
+        Snapshot {
+            version: SCHEMA_VERSION,
+            visible_board: game.visible_board(),
+            game: game.clone(),
+        }
+    }
+
+    pub fn to_json(&self) -> String {
+        serde_json::to_string_pretty(self).expect("snapshots always serialize")
+    }
+
+    pub fn from_json(json: &str) -> Result<Self, SnapshotError> {
+        // Check the version first, so older layouts get a clear error.
+        let Version { version } = serde_json::from_str(json)?;
+        if !(MIN_SCHEMA_VERSION..=SCHEMA_VERSION).contains(&version) {
+            return Err(SnapshotError::UnsupportedVersion(version));
+        }
+        let mut snapshot: Snapshot = serde_json::from_str(json)?;
+        snapshot.version = SCHEMA_VERSION;
+        Ok(snapshot)
+    }
+
+    // The snapshot without anything players shouldn't see during the hand:
+    // the seeds, the deck, the board while it isn't all face up and the hole
+    // cards of everyone that hasn't won a pot at showdown.
+    pub fn public(&self) -> Self {
+        let mut game = self.game.clone();
+        if self.visible_board.len() < 5 {
+            game.board = None;
+        }
+        game.seed = 0;
+        game.hand_seed = 0;
+        game.deck = Deck { cards: Vec::new() };
+        let winners: Vec<u64> = game.payouts
+            .iter()
+            .filter(|p| p.hand.is_some())
+            .map(|p| p.id)
+            .collect();
+        for p in game.players.iter_mut() {
+            if !winners.contains(&p.id) {
+                p.hand = None;
+            }
+        }
+        Snapshot {
+            version: self.version,
+            visible_board: self.visible_board.clone(),
+            game,
+        }
+    }
+}
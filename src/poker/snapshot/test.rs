@@ -0,0 +1,80 @@
+use super::*;
+
+fn game() -> Game {
+    let mut game = Game::with_seed(&[381_249_562_934_312_961, 2, 3], 1000, Blinds::new(5, 10), 7);
+    game.new_hand().unwrap();
+    game.act(381_249_562_934_312_961, Action::Raise(30)).unwrap();
+    game.act(2, Action::Call).unwrap();
+    game.act(3, Action::Call).unwrap();
+    game
+}
+
+#[test]
+fn round_trip() {
+    let game = game();
+    let json = Snapshot::new(&game).to_json();
+    let restored = Snapshot::from_json(&json).unwrap().game;
+    assert_eq!(Snapshot::new(&restored).to_json(), json);
+    assert_eq!(restored.street, Street::Flop);
+    assert_eq!(restored.current_player().map(|p| p.id), game.current_player().map(|p| p.id));
+    assert_eq!(restored.deck, game.deck);
+    assert_eq!(restored.pots(), game.pots());
+}
+
+#[test]
+fn restored_game_plays_on() {
+    let mut game = game();
+    let mut restored = Snapshot::from_json(&Snapshot::new(&game).to_json()).unwrap().game;
+    for g in [&mut game, &mut restored].iter_mut() {
+        while !g.over {
+            let id = g.current_player().unwrap().id;
+            g.act(id, Action::Check).unwrap();
+        }
+        g.new_hand().unwrap();
+    }
+    assert_eq!(Snapshot::new(&game).to_json(), Snapshot::new(&restored).to_json());
+}
+
+#[test]
+fn ids_are_strings() {
+    let value: serde_json::Value = serde_json::from_str(&Snapshot::new(&game()).to_json()).unwrap();
+    let seats = &value["game"]["players"]["seats"];
+    assert_eq!(seats[0]["id"], "381249562934312961");
+    assert_eq!(value["game"]["small_blind_player"], "2");
+    assert!(seats[0]["hand"][0].as_str().unwrap().len() == 2);
+}
+
+#[test]
+fn golden() {
+    let json = Snapshot::new(&game()).to_json();
+    assert_eq!(json, include_str!("golden.json").trim_end());
+}
+
+#[test]
+fn reads_version_1() {
+    let snapshot = Snapshot::from_json(include_str!("golden_v1.json")).unwrap();
+    assert_eq!(snapshot.version, SCHEMA_VERSION);
+    assert_eq!(snapshot.game.structure, structure::Structure::NoLimit);
+    assert_eq!(Snapshot::new(&snapshot.game).to_json(), Snapshot::new(&game()).to_json());
+}
+
+#[test]
+fn public_view() {
+    let public = Snapshot::new(&game()).public();
+    assert_eq!(public.visible_board.len(), 3);
+    assert_eq!(public.game.board, None);
+    assert!(public.game.deck.cards().is_empty());
+    assert_eq!(public.game.seed, 0);
+    assert_eq!(public.game.hand_seed, 0);
+    assert!(public.game.players.iter().all(|p| p.hand.is_none()));
+}
+
+#[test]
+fn version_check() {
+    let json = Snapshot::new(&game()).to_json().replacen("\"version\": 4", "\"version\": 5", 1);
+    match Snapshot::from_json(&json) {
+        Err(SnapshotError::UnsupportedVersion(5)) => {}
+        other => panic!("unexpected {:?}", other.map(|s| s.version)),
+    }
+    assert!(Snapshot::from_json("{\"version\": 4}").is_err());
+}
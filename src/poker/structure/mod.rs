@@ -0,0 +1,109 @@
+// Betting structures, deciding how much a player may bet or raise to.
+//
+// - No-limit: anything from a full raise, the size of the last full raise or
+//   the big blind, up to the whole stack.
+// - Pot-limit: from a full raise up to raising by the pot after calling.
+// - Fixed-limit: bets and raises of the small bet (the big blind) preflop and
+//   on the flop, of the big bet (twice the big blind) on the turn and river,
+//   and at most `cap` of them in a betting round, the big blind counting as
+//   the first bet preflop.
+//
+// A player who can't afford the minimum can still go all-in for less.
+use std::fmt;
+use std::str::FromStr;
+
+use serde::{Deserialize, Serialize};
+
+use super::game::Street;
+
+#[cfg(test)]
+mod test;
+
+pub const DEFAULT_CAP: u32 = 4;
+
+#[derive(PartialEq, Eq, Clone, Copy, Debug, Default, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "kebab-case")]
+pub enum Structure {
+    #[default]
+    NoLimit,
+    PotLimit,
+    FixedLimit { cap: u32 },
+}
+
+// What a structure needs to know about the betting round.
+#[derive(PartialEq, Eq, Clone, Copy, Debug)]
+pub struct Betting {
+    pub street: Street,
+    pub big_blind: u32,
+    // Highest bet of the round and size of its last full raise
+    pub bet: u32,
+    pub min_raise: u32,
+    // Bets and full raises made in the round
+    pub raises: u32,
+    // Chips in the pot, bets of the round included
+    pub pot: u32,
+    // Bet and stack of the player to act
+    pub player_bet: u32,
+    pub stack: u32,
+}
+
+// Smallest and largest amounts a player can bet or raise to. The minimum is
+// lower than a full raise only when it puts the player all-in.
+#[derive(PartialEq, Eq, Clone, Copy, Debug)]
+pub struct Limits {
+    pub min: u32,
+    pub max: u32,
+}
+
+impl Structure {
+    // None when the player can only call, check or fold.
+    pub fn limits(&self, b: &Betting) -> Option<Limits> {
+        let all_in = b.player_bet + b.stack;
+        let full = b.bet + b.min_raise;
+        let (min, max) = match *self {
+            Structure::NoLimit => (full, all_in),
+            Structure::PotLimit => {
+                let to_call = b.bet - b.player_bet;
+                (full, b.bet + b.pot + to_call)
+            }
+            Structure::FixedLimit { cap } => {
+                if b.raises >= cap {
+                    return None;
+                }
+                let size = match b.street {
+                    Street::Preflop | Street::Flop => b.big_blind,
+                    Street::Turn | Street::River | Street::Showdown => b.big_blind * 2,
+                };
+                (b.bet + size, b.bet + size)
+            }
+        };
+        let max = max.min(all_in);
+        if max <= b.bet {
+            return None;
+        }
+        Some(Limits { min: min.min(max), max })
+    }
+}
+
+impl fmt::Display for Structure {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Structure::NoLimit => write!(f, "no-limit"),
+            Structure::PotLimit => write!(f, "pot-limit"),
+            Structure::FixedLimit { cap } => write!(f, "fixed-limit capped at {} bets", cap),
+        }
+    }
+}
+
+impl FromStr for Structure {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "nl" | "no-limit" | "nolimit" => Ok(Structure::NoLimit),
+            "pl" | "pot-limit" | "potlimit" => Ok(Structure::PotLimit),
+            "fl" | "fixed-limit" | "fixedlimit" | "limit" => Ok(Structure::FixedLimit { cap: DEFAULT_CAP }),
+            _ => Err(()),
+        }
+    }
+}
@@ -0,0 +1,77 @@
+use super::*;
+
+// Blinds 5/10, facing the big blind with 1000 chips.
+fn preflop() -> Betting {
+    Betting {
+        street: Street::Preflop,
+        big_blind: 10,
+        bet: 10,
+        min_raise: 10,
+        raises: 1,
+        pot: 15,
+        player_bet: 0,
+        stack: 1000,
+    }
+}
+
+#[test]
+fn no_limit() {
+    let nl = Structure::NoLimit;
+    assert_eq!(nl.limits(&preflop()), Some(Limits { min: 20, max: 1000 }));
+
+    // After a raise to 40 the next full raise adds 30 more.
+    let b = Betting { bet: 40, min_raise: 30, raises: 2, pot: 55, ..preflop() };
+    assert_eq!(nl.limits(&b), Some(Limits { min: 70, max: 1000 }));
+
+    // Opening the flop.
+    let b = Betting { street: Street::Flop, bet: 0, min_raise: 10, raises: 0, pot: 30, ..preflop() };
+    assert_eq!(nl.limits(&b), Some(Limits { min: 10, max: 1000 }));
+
+    // A short stack can only go all-in, and one that can't beat the bet can't raise.
+    assert_eq!(nl.limits(&Betting { stack: 15, ..preflop() }), Some(Limits { min: 15, max: 15 }));
+    assert_eq!(nl.limits(&Betting { stack: 10, ..preflop() }), None);
+}
+
+#[test]
+fn pot_limit() {
+    let pl = Structure::PotLimit;
+    // Calling 10 makes the pot 25, raising by that is a raise to 35.
+    assert_eq!(pl.limits(&preflop()), Some(Limits { min: 20, max: 35 }));
+
+    // The small blind completes 5 into a pot of 15 and raises 20 on top.
+    let b = Betting { player_bet: 5, stack: 995, ..preflop() };
+    assert_eq!(pl.limits(&b), Some(Limits { min: 20, max: 30 }));
+
+    // Betting into 100 on the flop.
+    let b = Betting { street: Street::Flop, bet: 0, min_raise: 10, raises: 0, pot: 100, ..preflop() };
+    assert_eq!(pl.limits(&b), Some(Limits { min: 10, max: 100 }));
+
+    // Facing a pot bet of 100 into 100: call 100, then raise by 300.
+    let b = Betting { street: Street::Flop, bet: 100, min_raise: 100, raises: 1, pot: 200, ..preflop() };
+    assert_eq!(pl.limits(&b), Some(Limits { min: 200, max: 400 }));
+    assert_eq!(pl.limits(&Betting { stack: 250, ..b }), Some(Limits { min: 200, max: 250 }));
+}
+
+#[test]
+fn fixed_limit() {
+    let fl = Structure::FixedLimit { cap: 4 };
+    assert_eq!(fl.limits(&preflop()), Some(Limits { min: 20, max: 20 }));
+
+    let b = Betting { street: Street::Turn, bet: 0, raises: 0, pot: 60, ..preflop() };
+    assert_eq!(fl.limits(&b), Some(Limits { min: 20, max: 20 }));
+    let b = Betting { street: Street::River, bet: 60, min_raise: 20, raises: 3, pot: 200, ..preflop() };
+    assert_eq!(fl.limits(&b), Some(Limits { min: 80, max: 80 }));
+
+    // Capped after the fourth bet, and short stacks raise all-in for less.
+    assert_eq!(fl.limits(&Betting { raises: 4, ..b }), None);
+    assert_eq!(fl.limits(&Betting { stack: 70, ..b }), Some(Limits { min: 70, max: 70 }));
+}
+
+#[test]
+fn parse() {
+    assert_eq!("nl".parse(), Ok(Structure::NoLimit));
+    assert_eq!("Pot-Limit".parse(), Ok(Structure::PotLimit));
+    assert_eq!("fl".parse(), Ok(Structure::FixedLimit { cap: DEFAULT_CAP }));
+    assert_eq!("spread".parse::<Structure>(), Err(()));
+    assert_eq!(Structure::FixedLimit { cap: 3 }.to_string(), "fixed-limit capped at 3 bets");
+}
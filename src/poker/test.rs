@@ -17,6 +17,24 @@ fn deck() {
     assert_eq!(deck.pop(), None); 
 }
 
+#[test]
+fn seeded_deck() {
+    let mut first = Deck::from_seed(1234);
+    let mut second = Deck::from_seed(1234);
+    assert_eq!(first.cards(), second.cards());
+    assert_ne!(first.cards(), Deck::from_seed(4321).cards());
+    assert_ne!(first.cards(), Deck::ordered().cards());
+
+    let mut indices: Vec<u8> = first.cards().iter().map(|c| c.index()).collect();
+    indices.sort_unstable();
+    assert_eq!(indices, (0..52).collect::<Vec<u8>>());
+
+    // Cards are dealt from the top.
+    let top = *first.cards().last().unwrap();
+    assert_eq!(first.pop(), Some(top));
+    assert_eq!(second.pop(), Some(top));
+}
+
 #[test]
 fn board() {
     let c1 = Card(Suit::Clubs, Rank::Ace);
@@ -41,3 +59,50 @@ fn player_ring() {
     assert_eq!(ring.next().id, 3);
     assert_eq!(ring.next().id, 1);
 }
+
+#[test]
+fn button() {
+    let mut ring = PlayerRing::new(&[1, 2, 3], 100);
+    assert_eq!(ring.button().id, 1);
+    ring.get_player_mut(2).unwrap().money = 0;
+    ring.move_button();
+    assert_eq!(ring.button().id, 3);
+    ring.move_button();
+    assert_eq!(ring.button().id, 1);
+    assert_eq!(ring.next_after(3, |p| p.money > 0).unwrap().id, 1);
+}
+
+#[test]
+fn add_and_remove_seats() {
+    let mut ring = PlayerRing::new(&[1, 2, 3], 100);
+    assert!(ring.add(4, 50));
+    assert!(!ring.add(4, 50));
+    assert_eq!(ring.iter().map(|p| p.id).collect::<Vec<u64>>(), vec![1, 2, 3, 4]);
+    assert_eq!(ring.get_player(4).unwrap().money, 50);
+
+    // The button of a player leaving goes back to the player before them.
+    ring.move_button();
+    assert_eq!(ring.button().id, 2);
+    assert_eq!(ring.remove(2).unwrap().id, 2);
+    assert_eq!(ring.button().id, 1);
+    ring.move_button();
+    assert_eq!(ring.button().id, 3);
+
+    // Seats before the button shift it back with them.
+    ring.remove(1);
+    assert_eq!(ring.button().id, 3);
+    ring.remove(3);
+    assert_eq!(ring.button().id, 4);
+    assert_eq!(ring.remove(3).map(|p| p.id), None);
+    assert_eq!(ring.len(), 1);
+}
+
+#[test]
+fn card_index() {
+    for i in 0..52 {
+        assert_eq!(Card::from_index(i).unwrap().index(), i);
+    }
+    assert_eq!(Card(Suit::Clubs, Rank::Two).index(), 0);
+    assert_eq!(Card(Suit::Spades, Rank::Ace).index(), 51);
+    assert_eq!(Card::from_index(52), None);
+}
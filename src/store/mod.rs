@@ -0,0 +1,68 @@
+// Keeps every record (a table, a guild's wallets) in its own JSON file named
+// after its id, rewritten whole after every change so a restart never loses
+// more than one action.
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use serde::{de::DeserializeOwned, Serialize};
+
+#[cfg(test)]
+mod test;
+
+pub struct Store {
+    dir: PathBuf,
+}
+
+impl Store {
+    pub fn open<P: AsRef<Path>>(dir: P) -> io::Result<Self> {
+        fs::create_dir_all(&dir)?;
+        Ok(Store { dir: dir.as_ref().to_path_buf() })
+    }
+
+    fn path(&self, id: u64) -> PathBuf {
+        self.dir.join(format!("{}.json", id))
+    }
+
+    // Writes to a temporary file first and renames it over the old one, so a
+    // crash halfway through leaves the previous state intact.
+    pub fn save<T: Serialize>(&self, id: u64, value: &T) -> io::Result<()> {
+        let json = serde_json::to_string(value)?;
+        let tmp = self.dir.join(format!("{}.json.tmp", id));
+        fs::write(&tmp, json)?;
+        fs::rename(tmp, self.path(id))
+    }
+
+    pub fn remove(&self, id: u64) -> io::Result<()> {
+        match fs::remove_file(self.path(id)) {
+            Err(why) if why.kind() != io::ErrorKind::NotFound => Err(why),
+            _ => Ok(()),
+        }
+    }
+
+    pub fn load<T: DeserializeOwned>(&self, id: u64) -> io::Result<T> {
+        let json = fs::read_to_string(self.path(id))?;
+        Ok(serde_json::from_str(&json)?)
+    }
+
+    // Every stored record by id, with the error of the ones that couldn't be
+    // read so the rest can still be restored.
+    pub fn load_all<T: DeserializeOwned>(&self) -> io::Result<Vec<(u64, io::Result<T>)>> {
+        let mut records = Vec::new();
+        for entry in fs::read_dir(&self.dir)? {
+            let path = entry?.path();
+            if path.extension().and_then(|e| e.to_str()) != Some("json") {
+                continue;
+            }
+            let id = path
+                .file_stem()
+                .and_then(|s| s.to_str())
+                .and_then(|s| s.parse().ok());
+            if let Some(id) = id {
+                records.push((id, self.load(id)));
+            }
+        }
+        records.sort_by_key(|(id, _)| *id);
+        Ok(records)
+    }
+}
@@ -0,0 +1,53 @@
+use std::process;
+
+use super::*;
+use crate::poker::{snapshot::Snapshot, Action, Blinds, Game};
+
+fn store(name: &str) -> Store {
+    let dir = std::env::temp_dir().join(format!("poker-store-{}-{}", name, process::id()));
+    let _ = fs::remove_dir_all(&dir);
+    Store::open(dir).unwrap()
+}
+
+fn game() -> Game {
+    let mut game = Game::with_seed(&[1, 2, 3], 1000, Blinds::new(5, 10), 3);
+    game.new_hand().unwrap();
+    game.act(1, Action::Call).unwrap();
+    game
+}
+
+#[test]
+fn save_and_load() {
+    let store = store("save");
+    let game = game();
+    store.save(42, &game).unwrap();
+    let loaded: Game = store.load(42).unwrap();
+    assert_eq!(Snapshot::new(&loaded).to_json(), Snapshot::new(&game).to_json());
+    assert_eq!(loaded.current_player().unwrap().id, 2);
+
+    // Saving again replaces the table.
+    let mut game = loaded;
+    game.act(2, Action::Call).unwrap();
+    store.save(42, &game).unwrap();
+    let loaded: Game = store.load(42).unwrap();
+    assert_eq!(loaded.current_player().unwrap().id, 3);
+}
+
+#[test]
+fn load_all_tables() {
+    let store = store("all");
+    store.save(7, &game()).unwrap();
+    store.save(3, &game()).unwrap();
+    fs::write(store.dir.join("5.json"), "not json").unwrap();
+    fs::write(store.dir.join("notes.txt"), "ignored").unwrap();
+
+    let tables = store.load_all::<Game>().unwrap();
+    let channels: Vec<u64> = tables.iter().map(|(c, _)| *c).collect();
+    assert_eq!(channels, vec![3, 5, 7]);
+    assert!(tables[0].1.is_ok());
+    assert!(tables[1].1.is_err());
+
+    store.remove(7).unwrap();
+    store.remove(7).unwrap();
+    assert_eq!(store.load_all::<Game>().unwrap().len(), 2);
+}
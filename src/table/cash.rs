@@ -0,0 +1,174 @@
+// Cash tables: the game starts as soon as the table opens and never ends,
+// hands being dealt whenever at least two players are in. Players sit down
+// with any buy-in in the table's range, top up or rebuy up to the largest
+// buy-in, sit out for a few hands and leave with their stack, between hands
+// or once the hand they are in is over. Opened with the table settings, e.g.
+// `a!cash 1000 buyin=400-2000 blinds=10/20`.
+use serde::{Deserialize, Serialize};
+
+use super::*;
+
+#[derive(PartialEq, Eq, Clone, Debug, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Cash {
+    // Chips players in the hand added, put on their stacks once it is over
+    pub top_ups: HashMap<u64, u32>,
+    // Players standing up once the hand is over
+    #[serde(with = "crate::poker::ids::list")]
+    pub leaving: Vec<u64>,
+}
+
+impl Table {
+    // Turns the table into a cash table with the host sitting down with the
+    // given stack. Buy-ins range from a fifth of the largest one unless the
+    // settings say otherwise.
+    pub fn with_cash(mut self, stack: u32) -> Self {
+        if self.settings.min_buy_in == 0 {
+            self.settings.min_buy_in = (self.settings.buy_in / 5).max(self.settings.blinds.big);
+        }
+        let game = Game::new(&self.seats, stack, self.settings.blinds)
+            .with_structure(self.settings.structure);
+        self.game = Some(game);
+        self.time_banks = self.seats.iter().map(|&id| (id, self.settings.time_bank)).collect();
+        self.cash = Some(Cash::default());
+        self
+    }
+
+    pub fn check_buy_in(&self, amount: u32) -> Result<(), LobbyError> {
+        if self.cash.is_none() {
+            return Err(LobbyError::NotCashTable);
+        }
+        let (min, max) = (self.settings.min_buy_in, self.settings.buy_in);
+        if amount < min || amount > max {
+            return Err(LobbyError::BuyIn { min, max });
+        }
+        Ok(())
+    }
+
+    // Seats a player at a cash table with the chips they bought in for.
+    pub fn sit_down(&mut self, user: u64, amount: u32) -> Result<(), TableError> {
+        self.check_buy_in(amount)?;
+        if self.is_seated(user) {
+            return Err(LobbyError::AlreadySeated.into());
+        }
+        if self.seats.len() >= self.settings.max_seats {
+            return Err(LobbyError::TableFull { max: self.settings.max_seats }.into());
+        }
+        self.seat(user, amount)
+    }
+
+    pub(super) fn topped_up(&self, user: u64) -> u32 {
+        self.cash.as_ref().and_then(|c| c.top_ups.get(&user)).copied().unwrap_or(0)
+    }
+
+    // Checks a player can add chips to their stack: up to the largest
+    // buy-in, and at least the smallest one when rebuying after going bust.
+    pub fn check_top_up(&self, user: u64, amount: u32) -> Result<(), TableError> {
+        if self.cash.is_none() {
+            return Err(LobbyError::NotCashTable.into());
+        }
+        let game = self.game.as_ref().ok_or(TableError::NotStarted)?;
+        let player = game.players.get_player(user).ok_or(LobbyError::NotSeated)?;
+        // Chips a player folded are lost, only the ones still in the hand count.
+        let live = if game.over || !player.is_playing { 0 } else { player.committed };
+        let stack = player.money + live + self.topped_up(user);
+        let room = self.settings.buy_in.saturating_sub(stack);
+        if amount == 0 || amount > room {
+            return Err(LobbyError::TopUp { max: room }.into());
+        }
+        if stack == 0 && amount < self.settings.min_buy_in {
+            return Err(LobbyError::BuyIn { min: self.settings.min_buy_in, max: self.settings.buy_in }.into());
+        }
+        Ok(())
+    }
+
+    // Adds chips to a player's stack, right away unless they are still in the
+    // hand being played.
+    pub fn top_up(&mut self, user: u64, amount: u32) -> Result<(), TableError> {
+        self.check_top_up(user, amount)?;
+        let game = self.game.as_mut().ok_or(TableError::NotStarted)?;
+        let player = game.players.get_player_mut(user).ok_or(LobbyError::NotSeated)?;
+        if game.over || !player.is_playing {
+            player.money += amount;
+        } else if let Some(cash) = self.cash.as_mut() {
+            *cash.top_ups.entry(user).or_insert(0) += amount;
+        }
+        Ok(())
+    }
+
+    // Stands a player up from a cash table, returning their stack if they
+    // leave right away. Players in the hand being played are sat out, and
+    // leave once it is over.
+    pub fn stand_up(&mut self, user: u64) -> Result<Option<u32>, TableError> {
+        if self.cash.is_none() {
+            return Err(LobbyError::NotCashTable.into());
+        }
+        if !self.is_seated(user) {
+            return Err(LobbyError::NotSeated.into());
+        }
+        let topped_up = self.topped_up(user);
+        match self.unseat(user) {
+            Ok(stack) => {
+                if let Some(cash) = self.cash.as_mut() {
+                    cash.top_ups.remove(&user);
+                }
+                Ok(Some(stack + topped_up))
+            }
+            Err(TableError::Game(GameError::HandInProgress)) => {
+                if let Some(cash) = self.cash.as_mut().filter(|c| !c.leaving.contains(&user)) {
+                    cash.leaving.push(user);
+                }
+                self.sit_out(user);
+                Ok(None)
+            }
+            Err(why) => Err(why),
+        }
+    }
+
+    pub fn is_leaving(&self, user: u64) -> bool {
+        self.cash.as_ref().map(|c| c.leaving.contains(&user)).unwrap_or(false)
+    }
+
+    // Once a hand is over, puts the chips topped up during it on the stacks
+    // and stands up the players leaving, returning what they take with them.
+    pub fn settle(&mut self) -> Vec<(u64, u32)> {
+        let (game, cash) = match (self.game.as_mut(), self.cash.as_mut()) {
+            (Some(game), Some(cash)) if game.over => (game, cash),
+            _ => return Vec::new(),
+        };
+        for (user, amount) in cash.top_ups.drain() {
+            if let Some(player) = game.players.get_player_mut(user) {
+                player.money += amount;
+            }
+        }
+        let leaving = std::mem::take(&mut cash.leaving);
+        leaving
+            .into_iter()
+            .filter_map(|user| self.unseat(user).ok().map(|stack| (user, stack)))
+            .collect()
+    }
+
+    // Whether a cash table between hands has at least two players to deal to
+    pub fn can_deal(&self) -> bool {
+        match &self.game {
+            Some(game) if game.over => {
+                game.players.iter().filter(|p| p.money > 0 && !self.is_sitting_out(p.id)).count() >= 2
+            }
+            _ => false,
+        }
+    }
+}
+
+impl Tables {
+    pub fn sit_down(&mut self, channel: u64, user: u64, amount: u32) -> Result<&mut Table, TableError> {
+        match self.seated_at(user) {
+            Some(seated) if seated != channel => {
+                return Err(TableError::SeatedElsewhere { channel: seated })
+            }
+            _ => (),
+        }
+        let table = self.tables.get_mut(&channel).ok_or(TableError::NoTable)?;
+        table.sit_down(user, amount)?;
+        Ok(table)
+    }
+}
@@ -0,0 +1,112 @@
+// Turn clock: every player gets `clock` seconds to act, then draws on a time
+// bank that lasts the whole game. Running out of time checks when possible and
+// folds otherwise, and players timing out repeatedly are sat out, acting for
+// them as soon as their turn comes until they are back.
+use serde::{Deserialize, Serialize};
+
+use super::*;
+
+// Seconds left when players are warned
+pub const WARNING: u64 = 10;
+// Timeouts in a row before a player is sat out
+pub const MAX_TIMEOUTS: u32 = 2;
+
+#[derive(PartialEq, Eq, Clone, Copy, Debug, Serialize, Deserialize)]
+pub struct Clock {
+    #[serde(with = "crate::poker::ids")]
+    pub player: u64,
+    // Number of the action the clock runs for, counting every action and deal
+    pub action: u32,
+    pub started: u64,
+    pub warned: bool,
+}
+
+#[derive(PartialEq, Eq, Clone, Copy, Debug)]
+pub enum ClockEvent {
+    Warning { player: u64, left: u64 },
+    TimedOut { player: u64, action: Action, sat_out: bool },
+    // Acted for a player already sitting out
+    SittingOut { player: u64, action: Action },
+}
+
+impl Table {
+    pub fn time_bank(&self, user: u64) -> u32 {
+        self.time_banks.get(&user).copied().unwrap_or(0)
+    }
+
+    pub fn is_sitting_out(&self, user: u64) -> bool {
+        self.sitting_out.contains(&user)
+    }
+
+    pub fn sit_out(&mut self, user: u64) {
+        if !self.is_sitting_out(user) {
+            self.sitting_out.push(user);
+        }
+    }
+
+    pub fn sit_in(&mut self, user: u64) {
+        self.sitting_out.retain(|&id| id != user);
+        self.timeouts.remove(&user);
+    }
+
+    // Deadline of the player to act, clock and time bank included
+    pub fn deadline(&self) -> Option<u64> {
+        let clock = self.clock?;
+        Some(clock.started + self.settings.clock as u64 + self.time_bank(clock.player) as u64)
+    }
+
+    // Starts the clock when the turn moved on, and acts for the player when
+    // their time is up. Called every second or so.
+    pub fn tick(&mut self, now: u64) -> Option<ClockEvent> {
+        let player = self.game.as_ref()?.current_player()?.id;
+        match self.clock {
+            Some(clock) if clock.action == self.actions => (),
+            _ => {
+                self.clock = Some(Clock { player, action: self.actions, started: now, warned: false });
+            }
+        }
+
+        if self.is_sitting_out(player) {
+            let action = self.timeout_action(player);
+            self.play(player, action, now).ok()?;
+            return Some(ClockEvent::SittingOut { player, action });
+        }
+
+        let deadline = self.deadline()?;
+        if now >= deadline {
+            let action = self.timeout_action(player);
+            self.play(player, action, now).ok()?;
+            self.time_banks.insert(player, 0);
+            let timeouts = self.timeouts.entry(player).or_insert(0);
+            *timeouts += 1;
+            let sat_out = *timeouts >= MAX_TIMEOUTS;
+            if sat_out {
+                self.sit_out(player);
+            }
+            return Some(ClockEvent::TimedOut { player, action, sat_out });
+        }
+
+        let clock = self.clock.as_mut()?;
+        if deadline - now <= WARNING && !clock.warned {
+            clock.warned = true;
+            return Some(ClockEvent::Warning { player, left: deadline - now });
+        }
+        None
+    }
+
+    fn timeout_action(&self, player: u64) -> Action {
+        match self.game.as_ref() {
+            Some(game) if game.to_call(player) == 0 => Action::Check,
+            _ => Action::Fold,
+        }
+    }
+
+    // Takes the time a player acted in past their clock from their time bank.
+    pub(super) fn charge_clock(&mut self, user: u64, now: u64) {
+        if let Some(clock) = self.clock.filter(|c| c.player == user && c.action == self.actions) {
+            let over = now.saturating_sub(clock.started + self.settings.clock as u64);
+            let bank = self.time_bank(user);
+            self.time_banks.insert(user, bank.saturating_sub(over as u32));
+        }
+    }
+}
@@ -0,0 +1,690 @@
+// Tables running in Discord channels, kept on disk so a restart of the bot
+// picks every hand up where it was left.
+use std::collections::HashMap;
+use std::fmt;
+use std::io;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use serde::{Deserialize, Serialize};
+
+use crate::poker::fair::{self, Shuffle};
+use crate::poker::showdown::rank_hands;
+use crate::poker::{Action, Card, Game, GameError, Street};
+use crate::store::Store;
+
+mod cash;
+mod clock;
+mod settings;
+mod tournament;
+pub use cash::Cash;
+pub use clock::{Clock, ClockEvent};
+pub use settings::{Settings, SettingsError, MAX_SEATS, MIN_SEATS};
+pub use tournament::{ordinal, LevelLength, Tournament};
+
+#[cfg(test)]
+mod test;
+
+#[derive(PartialEq, Eq, Clone, Copy, Debug)]
+pub enum LobbyError {
+    AlreadySeated,
+    NotSeated,
+    TableFull { max: usize },
+    AlreadyStarted,
+    NotHost,
+    NotEnoughPlayers { seated: usize, min: usize },
+    NotCashTable,
+    BuyIn { min: u32, max: u32 },
+    TopUp { max: u32 },
+}
+
+impl fmt::Display for LobbyError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            LobbyError::AlreadySeated =>
+                write!(f, "you are already seated at this table"),
+            LobbyError::NotSeated =>
+                write!(f, "you are not seated at this table"),
+            LobbyError::TableFull { max } =>
+                write!(f, "the table is full, it seats {} players", max),
+            LobbyError::AlreadyStarted =>
+                write!(f, "the game has already started"),
+            LobbyError::NotHost =>
+                write!(f, "only the host can do this"),
+            LobbyError::NotEnoughPlayers { seated, min } =>
+                write!(f, "{} players are not enough to play poker, need at least {}", seated, min),
+            LobbyError::NotCashTable =>
+                write!(f, "this isn't a cash table"),
+            LobbyError::BuyIn { min, max } =>
+                write!(f, "the buy-in must be between {} and {} chips", min, max),
+            LobbyError::TopUp { max: 0 } =>
+                write!(f, "your stack is already at the largest buy-in"),
+            LobbyError::TopUp { max } =>
+                write!(f, "you can add up to {} chips to your stack", max),
+        }
+    }
+}
+
+impl std::error::Error for LobbyError {}
+
+#[derive(PartialEq, Eq, Clone, Copy, Debug)]
+pub enum TableError {
+    NoTable,
+    ChannelTaken,
+    SeatedElsewhere { channel: u64 },
+    NotStarted,
+    Lobby(LobbyError),
+    Game(GameError),
+}
+
+impl fmt::Display for TableError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            TableError::NoTable =>
+                write!(f, "there is no table in this channel"),
+            TableError::ChannelTaken =>
+                write!(f, "there is already a table in this channel"),
+            TableError::SeatedElsewhere { channel } =>
+                write!(f, "you are already seated at the table in <#{}>", channel),
+            TableError::NotStarted =>
+                write!(f, "the game hasn't started yet"),
+            TableError::Lobby(why) =>
+                why.fmt(f),
+            TableError::Game(why) =>
+                why.fmt(f),
+        }
+    }
+}
+
+impl std::error::Error for TableError {}
+
+impl From<LobbyError> for TableError {
+    fn from(why: LobbyError) -> Self {
+        TableError::Lobby(why)
+    }
+}
+
+impl From<GameError> for TableError {
+    fn from(why: GameError) -> Self {
+        TableError::Game(why)
+    }
+}
+
+// Seconds since the unix epoch
+pub fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
+
+// A table opens as a lobby players join and leave, until the host starts the
+// game with everyone seated.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Table {
+    #[serde(with = "crate::poker::ids")]
+    pub guild: u64,
+    // A channel or a thread
+    #[serde(with = "crate::poker::ids")]
+    pub channel: u64,
+    #[serde(with = "crate::poker::ids")]
+    pub host: u64,
+    pub settings: Settings,
+    #[serde(with = "crate::poker::ids::list")]
+    pub seats: Vec<u64>,
+    pub game: Option<Game>,
+    // Shuffle of the current hand's deck, revealed once the hand is over
+    #[serde(default)]
+    pub shuffle: Option<Shuffle>,
+    // Shuffle of the next hand, its server seed committed to before players
+    // add their entropy
+    #[serde(default)]
+    pub next_shuffle: Option<Shuffle>,
+    // When the table last changed, in seconds since the unix epoch
+    #[serde(default)]
+    pub active: u64,
+    // Actions taken and hands dealt, which tell the clock when a turn starts
+    #[serde(default)]
+    pub actions: u32,
+    #[serde(default)]
+    pub clock: Option<Clock>,
+    // Seconds left in each player's time bank
+    #[serde(default)]
+    pub time_banks: HashMap<u64, u32>,
+    // Timeouts in a row of each player
+    #[serde(default)]
+    pub timeouts: HashMap<u64, u32>,
+    #[serde(default, with = "crate::poker::ids::list")]
+    pub sitting_out: Vec<u64>,
+    // Set for sit-and-go tournaments
+    #[serde(default)]
+    pub tournament: Option<Tournament>,
+    // Channel of the multi-table tournament the table is part of
+    #[serde(default, with = "crate::poker::ids::option")]
+    pub director: Option<u64>,
+    // Set for cash tables
+    #[serde(default)]
+    pub cash: Option<Cash>,
+}
+
+impl Table {
+    pub fn open(guild: u64, channel: u64, host: u64, settings: Settings) -> Self {
+        Table {
+            guild,
+            channel,
+            host,
+            settings,
+            seats: vec![host],
+            game: None,
+            shuffle: None,
+            next_shuffle: Some(Shuffle::new(vec![channel.to_string(), String::from("1")])),
+            active: now(),
+            actions: 0,
+            clock: None,
+            time_banks: HashMap::new(),
+            timeouts: HashMap::new(),
+            sitting_out: Vec::new(),
+            tournament: None,
+            director: None,
+            cash: None,
+        }
+    }
+
+    pub fn is_seated(&self, user: u64) -> bool {
+        self.seats.contains(&user)
+    }
+
+    pub fn join(&mut self, user: u64) -> Result<(), LobbyError> {
+        if self.game.is_some() {
+            return Err(LobbyError::AlreadyStarted);
+        }
+        if self.is_seated(user) {
+            return Err(LobbyError::AlreadySeated);
+        }
+        if self.seats.len() >= self.settings.max_seats {
+            return Err(LobbyError::TableFull { max: self.settings.max_seats });
+        }
+        self.seats.push(user);
+        Ok(())
+    }
+
+    // The next player seated becomes host when the host leaves.
+    pub fn leave(&mut self, user: u64) -> Result<(), LobbyError> {
+        if self.game.is_some() {
+            return Err(LobbyError::AlreadyStarted);
+        }
+        if !self.is_seated(user) {
+            return Err(LobbyError::NotSeated);
+        }
+        self.seats.retain(|&id| id != user);
+        if self.host == user {
+            if let Some(&next) = self.seats.first() {
+                self.host = next;
+            }
+        }
+        Ok(())
+    }
+
+    // Starts the game and deals its first hand.
+    pub fn start(&mut self, user: u64) -> Result<&Game, LobbyError> {
+        if self.game.is_some() {
+            return Err(LobbyError::AlreadyStarted);
+        }
+        if user != self.host {
+            return Err(LobbyError::NotHost);
+        }
+        if self.seats.len() < self.settings.min_seats {
+            return Err(LobbyError::NotEnoughPlayers {
+                seated: self.seats.len(),
+                min: self.settings.min_seats,
+            });
+        }
+        let stack = match self.tournament.as_mut() {
+            Some(tournament) => {
+                tournament.started = now();
+                tournament.stack
+            }
+            None => self.settings.buy_in,
+        };
+        let game = Game::new(&self.seats, stack, self.settings.blinds)
+            .with_structure(self.settings.structure);
+        self.game = Some(game);
+        self.time_banks = self.seats.iter().map(|&id| (id, self.settings.time_bank)).collect();
+        self.deal().expect("every seated player has chips");
+        Ok(self.game.as_ref().unwrap())
+    }
+
+    // Seats a player with their stack at a running table, dealt in from the
+    // next hand.
+    pub fn seat(&mut self, user: u64, stack: u32) -> Result<(), TableError> {
+        if self.is_seated(user) {
+            return Err(LobbyError::AlreadySeated.into());
+        }
+        let game = self.game.as_mut().ok_or(TableError::NotStarted)?;
+        game.seat(user, stack)?;
+        self.seats.push(user);
+        self.time_banks.insert(user, self.settings.time_bank);
+        Ok(())
+    }
+
+    // Takes a player not in the hand being played off a running table,
+    // returning their stack.
+    pub fn unseat(&mut self, user: u64) -> Result<u32, TableError> {
+        let game = self.game.as_mut().ok_or(TableError::NotStarted)?;
+        let stack = game.unseat(user)?.money;
+        self.seats.retain(|&id| id != user);
+        self.time_banks.remove(&user);
+        self.timeouts.remove(&user);
+        self.sitting_out.retain(|&id| id != user);
+        if self.host == user {
+            if let Some(&next) = self.seats.first() {
+                self.host = next;
+            }
+        }
+        Ok(stack)
+    }
+
+    // Deals the next hand from the shuffle committed to before it, with the
+    // channel, the hand number and whatever the players added as entropy, and
+    // draws the server seed of the hand after. Players sitting out at a cash
+    // table aren't dealt in.
+    pub fn deal(&mut self) -> Result<(), TableError> {
+        let game = self.game.as_mut().ok_or(TableError::NotStarted)?;
+        if self.cash.is_some() {
+            for p in game.players.iter_mut() {
+                p.sitting_out = self.sitting_out.contains(&p.id);
+            }
+        }
+        let hand = game.hand_number + 1;
+        let shuffle = match self.next_shuffle.take() {
+            Some(shuffle) => shuffle,
+            // Tables saved before seeds were committed to ahead of time
+            None => Shuffle::new(vec![self.channel.to_string(), hand.to_string()]),
+        };
+        if let Err(why) = game.new_hand_with_deck(shuffle.deck()) {
+            self.next_shuffle = Some(shuffle);
+            return Err(why.into());
+        }
+        self.shuffle = Some(shuffle);
+        self.next_shuffle = Some(Shuffle::new(vec![self.channel.to_string(), (hand + 1).to_string()]));
+        self.actions += 1;
+        Ok(())
+    }
+
+    // Mixes a player's input, or the id of a message of theirs, into the
+    // shuffle of the next hand. False when nothing was added.
+    pub fn add_entropy(&mut self, input: &str) -> bool {
+        match self.next_shuffle.as_mut() {
+            Some(shuffle) => shuffle.add_entropy(input),
+            None => false,
+        }
+    }
+
+    // Posted as soon as the server seed of the next hand is drawn
+    pub fn next_commitment(&self) -> Option<String> {
+        let shuffle = self.next_shuffle.as_ref()?;
+        let hand = self.game.as_ref().map(|g| g.hand_number + 1).unwrap_or(1);
+        Some(format!(
+            "Hand #{} is dealt from a server seed committed to as `{}`, mix in your own entropy with `a!entropy <anything>`.",
+            hand,
+            fair::to_hex(&shuffle.commitment()),
+        ))
+    }
+
+    // Acting is also how players sitting out come back.
+    pub fn act(&mut self, user: u64, action: Action, now: u64) -> Result<(), TableError> {
+        self.play(user, action, now)?;
+        self.sit_in(user);
+        Ok(())
+    }
+
+    fn play(&mut self, user: u64, action: Action, now: u64) -> Result<(), TableError> {
+        let game = self.game.as_mut().ok_or(TableError::NotStarted)?;
+        game.act(user, action)?;
+        self.charge_clock(user, now);
+        self.actions += 1;
+        Ok(())
+    }
+
+    // The only player left with chips once nobody else can play a hand.
+    // Cash tables wait for more players instead.
+    pub fn winner(&self) -> Option<u64> {
+        let game = self.game.as_ref().filter(|_| self.cash.is_none())?;
+        let mut left = game.players.iter().filter(|p| p.money > 0);
+        match (left.next(), left.next()) {
+            (Some(p), None) => Some(p.id),
+            _ => None,
+        }
+    }
+
+    // Posted when a hand is dealt, before anyone acts, with the commitment to
+    // the seed of the hand after
+    pub fn commitment(&self) -> Option<String> {
+        let game = self.game.as_ref()?;
+        let shuffle = self.shuffle.as_ref()?;
+        let mut text = format!(
+            "Hand #{} is dealt from the server seed committed to as `{}` and {} pieces of entropy, the seed is revealed after the hand.",
+            game.hand_number,
+            fair::to_hex(&shuffle.commitment()),
+            shuffle.entropy.len(),
+        );
+        if let Some(next) = self.next_commitment() {
+            text.push('\n');
+            text.push_str(&next);
+        }
+        Some(text)
+    }
+
+    // Hands shown down, pots won and the revealed shuffle of a finished hand
+    pub fn results(&self) -> Option<String> {
+        let game = self.game.as_ref().filter(|g| g.over)?;
+        let mut lines = vec![format!("**Hand #{}** results:", game.hand_number)];
+        let board: Vec<String> = game.visible_board().into_iter().map(pretty).collect();
+        if !board.is_empty() {
+            lines.push(format!("Board: {}", board.join(" ")));
+        }
+        for (id, best) in rank_hands(&game.players, game.board) {
+            if let Some((c1, c2)) = game.players.get_player(id).and_then(|p| p.hand).map(|h| h.cards()) {
+                lines.push(format!("<@{}> shows {} {}, {}", id, pretty(c1), pretty(c2), best.combo));
+            }
+        }
+        for payout in &game.payouts {
+            let pot = match payout.pot {
+                0 => String::from("the main pot"),
+                n => format!("side pot {}", n),
+            };
+            match payout.hand {
+                Some(best) => lines.push(format!("<@{}> wins {} from {} with {}", payout.id, payout.amount, pot, best.combo)),
+                None => lines.push(format!("<@{}> wins {} from {}", payout.id, payout.amount, pot)),
+            }
+        }
+        if let Some(shuffle) = &self.shuffle {
+            let seed = fair::to_hex(&shuffle.server_seed);
+            let commitment = fair::to_hex(&shuffle.commitment());
+            lines.push(format!(
+                "Check the deck with `a!verify {} {} {}`",
+                seed,
+                commitment,
+                shuffle.entropy.join(" "),
+            ));
+        }
+        Some(lines.join("\n"))
+    }
+
+    fn lobby_status(&self) -> String {
+        let seats: Vec<String> = self.seats.iter().map(|id| format!("<@{}>", id)).collect();
+        let title = match &self.tournament {
+            Some(tournament) => format!("**Tournament open**: {}, {}", tournament, self.settings),
+            None => format!("**Table open**: {}", self.settings),
+        };
+        let mut status = format!(
+            "{}\nSeated ({}/{}): {}\nJoin with `a!join`, <@{}> starts the game with `a!start`.",
+            title,
+            self.seats.len(),
+            self.settings.max_seats,
+            seats.join(", "),
+            self.host,
+        );
+        if let Some(commitment) = self.next_commitment() {
+            status.push('\n');
+            status.push_str(&commitment);
+        }
+        status
+    }
+
+    // Chips each player would take from the table if it closed now: their
+    // buy-in before the game starts, and their stack with whatever they put in
+    // a hand that isn't over or topped up during it.
+    pub fn stacks(&self) -> Vec<(u64, u32)> {
+        match &self.game {
+            None => self.seats.iter().map(|&id| (id, self.settings.buy_in)).collect(),
+            Some(game) => game
+                .players
+                .iter()
+                .map(|p| {
+                    let stack = if game.over { p.money } else { p.money + p.committed };
+                    (p.id, stack + self.topped_up(p.id))
+                })
+                .collect(),
+        }
+    }
+
+    // Private message showing a player their hole cards in the current hand
+    pub fn hole_cards(&self, user: u64) -> Option<String> {
+        let game = self.game.as_ref()?;
+        let hand = game.players.get_player(user)?.hand?;
+        let (c1, c2) = hand.cards();
+        Some(format!(
+            "Your cards for hand #{} in <#{}>: **{} {}**",
+            game.hand_number,
+            self.channel,
+            pretty(c1),
+            pretty(c2),
+        ))
+    }
+
+    // Summary of the table, written as a Discord message.
+    pub fn status(&self) -> String {
+        let game = match &self.game {
+            Some(game) => game,
+            None => return self.lobby_status(),
+        };
+        let mut lines = Vec::new();
+        if game.over && game.hand_number == 0 {
+            lines.push(format!("**Cash table open**: {}", self.settings));
+            lines.extend(self.next_commitment());
+        } else if game.over {
+            lines.push(format!("**Hand #{}** is over.", game.hand_number));
+        } else {
+            lines.push(format!("**Hand #{}**, {}", game.hand_number, street_name(game.street)));
+            let board: Vec<String> = game.visible_board().into_iter().map(pretty).collect();
+            if !board.is_empty() {
+                lines.push(format!("Board: {}", board.join(" ")));
+            }
+            lines.push(format!("Pot: {}", game.pot()));
+        }
+        if let Some(tournament) = &self.tournament {
+            let blinds = game.blinds;
+            let mut line = format!("Level {} of {}: blinds {}/{}", tournament.level + 1, tournament.levels.len(), blinds.small, blinds.big);
+            if blinds.ante > 0 {
+                line.push_str(&format!(", ante {}", blinds.ante));
+            }
+            lines.push(line);
+        }
+
+        let button = game.players.button().id;
+        let entrants = game.players.iter().count();
+        for p in game.players.iter() {
+            let out = self.tournament.as_ref().and_then(|t| t.eliminated.iter().position(|&id| id == p.id));
+            if let Some(i) = out {
+                lines.push(format!("<@{}>: out in {} place", p.id, ordinal(entrants - i)));
+                continue;
+            }
+            let mut line = format!("<@{}>: {} chips", p.id, p.money);
+            if p.id == button {
+                line.push_str(" (button)");
+            }
+            if self.is_leaving(p.id) {
+                line.push_str(", leaving after this hand");
+            } else if self.is_sitting_out(p.id) {
+                line.push_str(", sitting out");
+            }
+            if self.topped_up(p.id) > 0 {
+                line.push_str(&format!(", topping up {}", self.topped_up(p.id)));
+            }
+            if !game.over {
+                if p.hand.is_none() {
+                    if p.money > 0 && !p.sitting_out {
+                        line.push_str(", dealt in next hand");
+                    }
+                } else if !p.is_playing {
+                    line.push_str(", folded");
+                } else if p.is_all_in() {
+                    line.push_str(", all-in");
+                } else if p.bet > 0 {
+                    line.push_str(&format!(", bet {}", p.bet));
+                }
+            }
+            lines.push(line);
+        }
+
+        if let Some(p) = game.current_player() {
+            let mut line = format!("Waiting for <@{}> to act", p.id);
+            let to_call = game.to_call(p.id);
+            if to_call > 0 {
+                line.push_str(&format!(", {} to call", to_call));
+            }
+            if let Some(limits) = game.limits(p.id) {
+                let verb = if game.bet == 0 { "bet" } else { "raise to" };
+                if limits.min == limits.max {
+                    line.push_str(&format!(", {} {}", verb, limits.min));
+                } else {
+                    line.push_str(&format!(", {} {} to {}", verb, limits.min, limits.max));
+                }
+            }
+            line.push('.');
+            lines.push(line);
+        } else if self.cash.is_some() && !self.can_deal() {
+            lines.push(String::from("Waiting for more players, sit down with `a!join <chips>`."));
+        }
+        lines.join("\n")
+    }
+}
+
+fn pretty(card: Card) -> String {
+    format!("{}{}", card.rank().short(), card.suit().symbol())
+}
+
+fn street_name(street: Street) -> &'static str {
+    match street {
+        Street::Preflop => "preflop",
+        Street::Flop => "on the flop",
+        Street::Turn => "on the turn",
+        Street::River => "on the river",
+        Street::Showdown => "at showdown",
+    }
+}
+
+// Outcome of `Tables::restore`
+#[derive(Debug, Default)]
+pub struct Restored {
+    pub tables: Vec<u64>,
+    pub failed: Vec<(u64, io::Error)>,
+}
+
+// Every table by channel, saved to the store on every change. A channel has
+// at most one table and a player sits at one table at a time, across guilds.
+pub struct Tables {
+    store: Store,
+    tables: HashMap<u64, Table>,
+}
+
+impl Tables {
+    pub fn new(store: Store) -> Self {
+        Tables {
+            store,
+            tables: HashMap::new(),
+        }
+    }
+
+    // Loads the stored tables that aren't running yet.
+    pub fn restore(&mut self) -> io::Result<Restored> {
+        let mut restored = Restored::default();
+        for (channel, table) in self.store.load_all::<Table>()? {
+            if self.tables.contains_key(&channel) {
+                continue;
+            }
+            match table {
+                Ok(table) => {
+                    self.tables.insert(channel, table);
+                    restored.tables.push(channel);
+                }
+                Err(why) => restored.failed.push((channel, why)),
+            }
+        }
+        Ok(restored)
+    }
+
+    pub fn get(&self, channel: u64) -> Option<&Table> {
+        self.tables.get(&channel)
+    }
+
+    // Changes to the table have to be followed by `save`.
+    pub fn get_mut(&mut self, channel: u64) -> Option<&mut Table> {
+        self.tables.get_mut(&channel)
+    }
+
+    // Channel of the table the user is seated at
+    pub fn seated_at(&self, user: u64) -> Option<u64> {
+        self.tables
+            .values()
+            .find(|t| t.is_seated(user))
+            .map(|t| t.channel)
+    }
+
+    pub fn open(&mut self, table: Table) -> Result<&mut Table, TableError> {
+        if self.tables.contains_key(&table.channel) {
+            return Err(TableError::ChannelTaken);
+        }
+        if let Some(channel) = self.seated_at(table.host) {
+            return Err(TableError::SeatedElsewhere { channel });
+        }
+        Ok(self.tables.entry(table.channel).or_insert(table))
+    }
+
+    pub fn join(&mut self, channel: u64, user: u64) -> Result<&mut Table, TableError> {
+        match self.seated_at(user) {
+            Some(seated) if seated != channel => {
+                return Err(TableError::SeatedElsewhere { channel: seated })
+            }
+            _ => (),
+        }
+        let table = self.tables.get_mut(&channel).ok_or(TableError::NoTable)?;
+        table.join(user)?;
+        Ok(table)
+    }
+
+    // Saves a table after a change, which counts as activity.
+    pub fn save(&mut self, channel: u64) -> io::Result<()> {
+        match self.tables.get_mut(&channel) {
+            Some(table) => {
+                table.active = now();
+                self.store.save(channel, table)
+            }
+            None => Ok(()),
+        }
+    }
+
+    pub fn remove(&mut self, channel: u64) -> io::Result<Option<Table>> {
+        self.store.remove(channel)?;
+        Ok(self.tables.remove(&channel))
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &Table> {
+        self.tables.values()
+    }
+
+    // Tables of a guild, by channel
+    pub fn in_guild(&self, guild: u64) -> Vec<&Table> {
+        let mut tables: Vec<&Table> = self.tables.values().filter(|t| t.guild == guild).collect();
+        tables.sort_by_key(|t| t.channel);
+        tables
+    }
+
+    // Channels of the tables nothing happened at for longer than allowed,
+    // lobbies and running games each with their own limit in seconds. Tables
+    // of multi-table tournaments are left to their director.
+    pub fn abandoned(&self, now: u64, lobby_idle: u64, game_idle: u64) -> Vec<u64> {
+        let mut channels: Vec<u64> = self
+            .tables
+            .values()
+            .filter(|t| t.director.is_none())
+            .filter(|t| {
+                let idle = if t.game.is_some() { game_idle } else { lobby_idle };
+                now.saturating_sub(t.active) > idle
+            })
+            .map(|t| t.channel)
+            .collect();
+        channels.sort_unstable();
+        channels
+    }
+}
@@ -0,0 +1,231 @@
+// Settings a table is opened with, written as `key=value` pairs after the
+// command, e.g. `a!create buyin=2000 blinds=10/20 ante=5 seats=3-6 clock=20`,
+// or `a!create structure=fixed-limit cap=3`. Cash tables take a range of
+// buy-ins, like `a!cash buyin=400-2000`.
+use std::fmt;
+use std::str::FromStr;
+
+use serde::{Deserialize, Serialize};
+
+use crate::poker::structure::Structure;
+use crate::poker::Blinds;
+
+pub const MIN_SEATS: usize = 2;
+pub const MAX_SEATS: usize = 10;
+pub const MIN_CLOCK: u32 = 10;
+pub const MAX_CLOCK: u32 = 600;
+
+#[derive(PartialEq, Eq, Clone, Copy, Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Settings {
+    // Chips every player brings to the table, taken from their wallet
+    pub buy_in: u32,
+    // Smallest buy-in of a cash table, whose `buy_in` is the largest. 0 for
+    // tables everyone buys in the same at.
+    pub min_buy_in: u32,
+    pub blinds: Blinds,
+    pub structure: Structure,
+    pub min_seats: usize,
+    pub max_seats: usize,
+    // Seconds players have to act before drawing on their time bank
+    pub clock: u32,
+    // Seconds of every player's time bank, for the whole game
+    pub time_bank: u32,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Settings {
+            buy_in: 1000,
+            min_buy_in: 0,
+            blinds: Blinds::new(5, 10),
+            structure: Structure::NoLimit,
+            min_seats: MIN_SEATS,
+            max_seats: 9,
+            clock: 30,
+            time_bank: 60,
+        }
+    }
+}
+
+#[derive(PartialEq, Eq, Clone, Debug)]
+pub enum SettingsError {
+    UnknownSetting(String),
+    InvalidValue { key: String, value: String },
+    Seats { min: usize, max: usize },
+    Blinds,
+    BuyIn { big_blind: u32 },
+    BuyInRange,
+    Clock,
+    Cap,
+    Levels,
+    Stack { big_blind: u32 },
+    Payouts,
+}
+
+impl fmt::Display for SettingsError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            SettingsError::UnknownSetting(key) =>
+                write!(f, "unknown setting `{}`", key),
+            SettingsError::InvalidValue { key, value } =>
+                write!(f, "`{}` isn't a valid value for `{}`", value, key),
+            SettingsError::Seats { min, max } =>
+                write!(f, "can't seat between {} and {} players, tables seat {} to {}",
+                    min, max, MIN_SEATS, MAX_SEATS),
+            SettingsError::Blinds =>
+                write!(f, "the small blind must be above 0 and at most the big blind"),
+            SettingsError::BuyIn { big_blind } =>
+                write!(f, "the buy-in must be at least the big blind ({})", big_blind),
+            SettingsError::BuyInRange =>
+                write!(f, "only cash tables take a range of buy-ins, like `buyin=400-2000`"),
+            SettingsError::Clock =>
+                write!(f, "the clock must be between {} and {} seconds, and the time bank at most {}",
+                    MIN_CLOCK, MAX_CLOCK, MAX_CLOCK),
+            SettingsError::Cap =>
+                write!(f, "only fixed-limit tables have a cap, of at least 1 bet"),
+            SettingsError::Levels =>
+                write!(f, "tournaments need at least one level, lasting at least 1 minute or hand"),
+            SettingsError::Stack { big_blind } =>
+                write!(f, "the starting stack must be at least the first big blind ({})", big_blind),
+            SettingsError::Payouts =>
+                write!(f, "the payouts must add up to 100%, paying at most {} places", MAX_SEATS),
+        }
+    }
+}
+
+impl std::error::Error for SettingsError {}
+
+impl Settings {
+    pub fn validate(&self) -> Result<(), SettingsError> {
+        self.validate_game()?;
+        if self.buy_in < self.blinds.big || (self.min_buy_in > 0 && self.min_buy_in < self.blinds.big) {
+            return Err(SettingsError::BuyIn { big_blind: self.blinds.big });
+        }
+        Ok(())
+    }
+
+    // Checks everything but how the buy-in compares to the blinds, tournament
+    // stacks being separate from their buy-in.
+    pub(super) fn validate_game(&self) -> Result<(), SettingsError> {
+        let (min, max) = (self.min_seats, self.max_seats);
+        if min < MIN_SEATS || max > MAX_SEATS || min > max {
+            return Err(SettingsError::Seats { min, max });
+        }
+        if self.blinds.small == 0 || self.blinds.small > self.blinds.big {
+            return Err(SettingsError::Blinds);
+        }
+        if let Structure::FixedLimit { cap: 0 } = self.structure {
+            return Err(SettingsError::Cap);
+        }
+        if self.clock < MIN_CLOCK || self.clock > MAX_CLOCK || self.time_bank > MAX_CLOCK {
+            return Err(SettingsError::Clock);
+        }
+        Ok(())
+    }
+
+    // Parses settings without checking them.
+    pub(super) fn read(s: &str) -> Result<Self, SettingsError> {
+        let mut settings = Settings::default();
+        // The cap applies to the structure, whichever comes first
+        let (caps, rest): (Vec<&str>, Vec<&str>) = s
+            .split_whitespace()
+            .partition(|setting| setting.to_lowercase().starts_with("cap="));
+        for setting in rest.into_iter().chain(caps) {
+            let mut parts = setting.splitn(2, '=');
+            let key = parts.next().unwrap_or_default().to_lowercase();
+            match parts.next() {
+                Some(value) => settings.set(&key, value)?,
+                None => return Err(SettingsError::UnknownSetting(key)),
+            }
+        }
+        Ok(settings)
+    }
+
+    // Turns down a range of buy-ins, for tables other than cash ones.
+    pub fn fixed_buy_in(self) -> Result<Self, SettingsError> {
+        if self.min_buy_in > 0 {
+            return Err(SettingsError::BuyInRange);
+        }
+        Ok(self)
+    }
+
+    fn set(&mut self, key: &str, value: &str) -> Result<(), SettingsError> {
+        let invalid = || SettingsError::InvalidValue {
+            key: String::from(key),
+            value: String::from(value),
+        };
+        let pair = |sep: char| -> Result<(&str, &str), SettingsError> {
+            let mut parts = value.splitn(2, sep);
+            match (parts.next(), parts.next()) {
+                (Some(a), Some(b)) => Ok((a, b)),
+                _ => Err(invalid()),
+            }
+        };
+        match key {
+            "buyin" => {
+                let (min, max) = match pair('-') {
+                    Ok((min, max)) => (min.parse().map_err(|_| invalid())?, max.parse().map_err(|_| invalid())?),
+                    Err(_) => (0, value.parse().map_err(|_| invalid())?),
+                };
+                if min > max || max == 0 {
+                    return Err(invalid());
+                }
+                self.min_buy_in = min;
+                self.buy_in = max;
+            }
+            "blinds" => {
+                let (small, big) = pair('/')?;
+                self.blinds.small = small.parse().map_err(|_| invalid())?;
+                self.blinds.big = big.parse().map_err(|_| invalid())?;
+            }
+            "ante" => self.blinds = self.blinds.with_ante(value.parse().map_err(|_| invalid())?),
+            "bbante" => {
+                self.blinds = self.blinds.with_big_blind_ante(value.parse().map_err(|_| invalid())?)
+            }
+            "seats" => {
+                let (min, max) = match pair('-') {
+                    Ok((min, max)) => (min, max),
+                    Err(_) => (value, value),
+                };
+                self.min_seats = min.parse().map_err(|_| invalid())?;
+                self.max_seats = max.parse().map_err(|_| invalid())?;
+            }
+            "structure" => self.structure = value.parse().map_err(|_| invalid())?,
+            "cap" => match &mut self.structure {
+                Structure::FixedLimit { cap } => *cap = value.parse().map_err(|_| invalid())?,
+                _ => return Err(SettingsError::Cap),
+            },
+            "clock" => self.clock = value.trim_end_matches('s').parse().map_err(|_| invalid())?,
+            "timebank" => self.time_bank = value.trim_end_matches('s').parse().map_err(|_| invalid())?,
+            _ => return Err(SettingsError::UnknownSetting(String::from(key))),
+        }
+        Ok(())
+    }
+}
+
+impl FromStr for Settings {
+    type Err = SettingsError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let settings = Settings::read(s)?;
+        settings.validate()?;
+        Ok(settings)
+    }
+}
+
+impl fmt::Display for Settings {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}, buy-in ", self.structure)?;
+        if self.min_buy_in > 0 {
+            write!(f, "{} to ", self.min_buy_in)?;
+        }
+        write!(f, "{}, blinds {}/{}", self.buy_in, self.blinds.small, self.blinds.big)?;
+        if self.blinds.ante > 0 {
+            let kind = if self.blinds.big_blind_ante { "big blind ante" } else { "ante" };
+            write!(f, ", {} {}", kind, self.blinds.ante)?;
+        }
+        write!(f, ", {} to {} seats", self.min_seats, self.max_seats)?;
+        write!(f, ", {}s to act with a {}s time bank", self.clock, self.time_bank)
+    }
+}
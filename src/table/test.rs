@@ -0,0 +1,569 @@
+use std::fs;
+use std::process;
+
+use super::*;
+use crate::poker::structure::Structure;
+use crate::poker::{fair, Action, Blinds, GameError, Street};
+
+fn store(name: &str) -> Store {
+    let dir = std::env::temp_dir().join(format!("poker-tables-{}-{}", name, process::id()));
+    let _ = fs::remove_dir_all(&dir);
+    Store::open(dir).unwrap()
+}
+
+fn store_again(name: &str) -> Store {
+    let dir = std::env::temp_dir().join(format!("poker-tables-{}-{}", name, process::id()));
+    Store::open(dir).unwrap()
+}
+
+fn table() -> Table {
+    let mut table = Table::open(1, 10, 1, Settings::default());
+    table.join(2).unwrap();
+    table.join(3).unwrap();
+    table.start(1).unwrap();
+    table
+}
+
+#[test]
+fn restore_after_restart() {
+    let mut tables = Tables::new(store("restore"));
+    tables.open(table()).unwrap();
+    tables.get_mut(10).unwrap().game.as_mut().unwrap().act(1, Action::Call).unwrap();
+    tables.save(10).unwrap();
+
+    let mut restarted = Tables::new(store_again("restore"));
+    let restored = restarted.restore().unwrap();
+    assert_eq!(restored.tables, vec![10]);
+    assert!(restored.failed.is_empty());
+    let table = restarted.get(10).unwrap();
+    assert_eq!(table.game.as_ref().unwrap().current_player().unwrap().id, 2);
+
+    // Restoring twice leaves running tables alone.
+    assert!(restarted.restore().unwrap().tables.is_empty());
+}
+
+#[test]
+fn remove_table() {
+    let mut tables = Tables::new(store("remove"));
+    tables.open(table()).unwrap();
+    tables.save(10).unwrap();
+    assert!(tables.remove(10).unwrap().is_some());
+    let mut restarted = Tables::new(store_again("remove"));
+    assert!(restarted.restore().unwrap().tables.is_empty());
+}
+
+#[test]
+fn status() {
+    let status = table().status();
+    assert!(status.starts_with("**Hand #1**, preflop"));
+    assert!(status.contains("<@1>: 1000 chips (button)"));
+    assert!(status.contains("<@3>: 990 chips, bet 10"));
+    assert!(status.ends_with("Waiting for <@1> to act, 10 to call, raise to 20 to 1000."));
+}
+
+#[test]
+fn lobby() {
+    let settings = Settings { min_seats: 3, max_seats: 4, ..Settings::default() };
+    let mut table = Table::open(1, 10, 1, settings);
+    assert_eq!(table.join(1), Err(LobbyError::AlreadySeated));
+    assert_eq!(table.leave(2), Err(LobbyError::NotSeated));
+    table.join(2).unwrap();
+    assert_eq!(table.start(1).unwrap_err(), LobbyError::NotEnoughPlayers { seated: 2, min: 3 });
+    table.join(3).unwrap();
+    table.join(4).unwrap();
+    assert_eq!(table.join(5), Err(LobbyError::TableFull { max: 4 }));
+    assert!(table.lobby_status().contains("Seated (4/4): <@1>, <@2>, <@3>, <@4>"));
+
+    // The host leaving hands the table to the next player seated.
+    table.leave(1).unwrap();
+    assert_eq!(table.host, 2);
+    assert_eq!(table.start(3).unwrap_err(), LobbyError::NotHost);
+
+    let game = table.start(2).unwrap();
+    assert_eq!(game.hand_number, 1);
+    let players: Vec<u64> = game.players.iter().map(|p| p.id).collect();
+    assert_eq!(players, vec![2, 3, 4]);
+    assert_eq!(table.join(5), Err(LobbyError::AlreadyStarted));
+    assert_eq!(table.leave(3), Err(LobbyError::AlreadyStarted));
+    assert_eq!(table.start(2).unwrap_err(), LobbyError::AlreadyStarted);
+}
+
+#[test]
+fn parse_settings() {
+    assert_eq!("".parse(), Ok(Settings::default()));
+    let settings: Settings = "buyin=2000 blinds=10/20 ante=5 seats=3-6".parse().unwrap();
+    assert_eq!(settings.buy_in, 2000);
+    assert_eq!(settings.blinds, Blinds::new(10, 20).with_ante(5));
+    assert_eq!((settings.min_seats, settings.max_seats), (3, 6));
+    assert_eq!(
+        settings.to_string(),
+        "no-limit, buy-in 2000, blinds 10/20, ante 5, 3 to 6 seats, 30s to act with a 60s time bank"
+    );
+    let settings: Settings = "clock=20s timebank=0".parse().unwrap();
+    assert_eq!((settings.clock, settings.time_bank), (20, 0));
+    assert_eq!("clock=5".parse::<Settings>(), Err(SettingsError::Clock));
+
+    let settings: Settings = "cap=3 structure=fl".parse().unwrap();
+    assert_eq!(settings.structure, Structure::FixedLimit { cap: 3 });
+    let settings: Settings = "structure=pot-limit".parse().unwrap();
+    assert_eq!(settings.structure, Structure::PotLimit);
+    assert_eq!("cap=3".parse::<Settings>(), Err(SettingsError::Cap));
+    assert_eq!("structure=fl cap=0".parse::<Settings>(), Err(SettingsError::Cap));
+
+    let settings: Settings = "bbante=20 Seats=6".parse().unwrap();
+    assert_eq!(settings.blinds, Blinds::new(5, 10).with_big_blind_ante(20));
+    assert_eq!((settings.min_seats, settings.max_seats), (6, 6));
+
+    assert_eq!("color=red".parse::<Settings>(), Err(SettingsError::UnknownSetting(String::from("color"))));
+    assert_eq!("blinds".parse::<Settings>(), Err(SettingsError::UnknownSetting(String::from("blinds"))));
+    assert_eq!(
+        "blinds=5".parse::<Settings>(),
+        Err(SettingsError::InvalidValue { key: String::from("blinds"), value: String::from("5") })
+    );
+    assert_eq!("seats=1-4".parse::<Settings>(), Err(SettingsError::Seats { min: 1, max: 4 }));
+    assert_eq!("seats=2-11".parse::<Settings>(), Err(SettingsError::Seats { min: 2, max: 11 }));
+    assert_eq!("blinds=20/10".parse::<Settings>(), Err(SettingsError::Blinds));
+    assert_eq!("buyin=5".parse::<Settings>(), Err(SettingsError::BuyIn { big_blind: 10 }));
+
+    let settings: Settings = "buyin=400-2000".parse().unwrap();
+    assert_eq!((settings.min_buy_in, settings.buy_in), (400, 2000));
+    assert!(settings.to_string().starts_with("no-limit, buy-in 400 to 2000, blinds 5/10"));
+    assert_eq!(settings.fixed_buy_in(), Err(SettingsError::BuyInRange));
+    assert_eq!("buyin=5-2000".parse::<Settings>(), Err(SettingsError::BuyIn { big_blind: 10 }));
+    assert_eq!(
+        "buyin=2000-400".parse::<Settings>(),
+        Err(SettingsError::InvalidValue { key: String::from("buyin"), value: String::from("2000-400") })
+    );
+}
+
+#[test]
+fn registry() {
+    let mut tables = Tables::new(store("registry"));
+    tables.open(Table::open(1, 10, 1, Settings::default())).unwrap();
+    assert_eq!(
+        tables.open(Table::open(1, 10, 2, Settings::default())).unwrap_err(),
+        TableError::ChannelTaken
+    );
+    assert_eq!(
+        tables.open(Table::open(1, 11, 1, Settings::default())).unwrap_err(),
+        TableError::SeatedElsewhere { channel: 10 }
+    );
+    tables.open(Table::open(1, 11, 2, Settings::default())).unwrap();
+    tables.open(Table::open(2, 20, 3, Settings::default())).unwrap();
+
+    assert_eq!(tables.join(10, 2).unwrap_err(), TableError::SeatedElsewhere { channel: 11 });
+    assert_eq!(tables.join(10, 1).unwrap_err(), TableError::Lobby(LobbyError::AlreadySeated));
+    assert_eq!(tables.join(12, 4).unwrap_err(), TableError::NoTable);
+    assert_eq!(tables.join(10, 4).unwrap().seats, vec![1, 4]);
+    assert_eq!(tables.seated_at(4), Some(10));
+    assert_eq!(tables.seated_at(5), None);
+
+    let channels: Vec<u64> = tables.in_guild(1).iter().map(|t| t.channel).collect();
+    assert_eq!(channels, vec![10, 11]);
+}
+
+#[test]
+fn abandoned_tables() {
+    let mut tables = Tables::new(store("abandoned"));
+    let mut lobby = Table::open(1, 10, 4, Settings::default());
+    lobby.active = 1000;
+    tables.open(lobby).unwrap();
+    let mut playing = table();
+    playing.channel = 11;
+    playing.active = 1000;
+    tables.open(playing).unwrap();
+
+    assert!(tables.abandoned(1100, 300, 600).is_empty());
+    assert_eq!(tables.abandoned(1400, 300, 600), vec![10]);
+    assert_eq!(tables.abandoned(1700, 300, 600), vec![10, 11]);
+
+    // Saving a change keeps the table alive.
+    tables.save(11).unwrap();
+    assert_eq!(tables.abandoned(1700, 300, 600), vec![10]);
+}
+
+#[test]
+fn stacks() {
+    let mut lobby = Table::open(1, 10, 1, Settings::default());
+    lobby.join(2).unwrap();
+    assert_eq!(lobby.stacks(), vec![(1, 1000), (2, 1000)]);
+
+    // Blinds and bets of a hand in progress go back to their players.
+    let mut table = table();
+    table.game.as_mut().unwrap().act(1, Action::Raise(40)).unwrap();
+    assert_eq!(table.stacks(), vec![(1, 1000), (2, 1000), (3, 1000)]);
+}
+
+#[test]
+fn hole_cards() {
+    let mut table = table();
+    let hand = "AsKh".parse().unwrap();
+    table.game.as_mut().unwrap().players.get_player_mut(2).unwrap().hand = Some(hand);
+    assert_eq!(table.hole_cards(2).unwrap(), "Your cards for hand #1 in <#10>: **A♠ K♥**");
+    assert_eq!(table.hole_cards(9), None);
+    assert_eq!(Table::open(1, 10, 1, Settings::default()).hole_cards(1), None);
+}
+
+#[test]
+fn hands() {
+    let mut table = table();
+    assert_eq!(Table::open(1, 11, 4, Settings::default()).act(4, Action::Fold, 0), Err(TableError::NotStarted));
+    assert_eq!(table.act(2, Action::Fold, 0), Err(TableError::Game(GameError::NotYourTurn)));
+    assert!(table.results().is_none());
+    let commitment = table.commitment().unwrap();
+    assert!(commitment.starts_with("Hand #1 is dealt from the server seed committed to as `"));
+    assert!(commitment.contains("and 2 pieces of entropy"));
+    assert!(commitment.contains("\nHand #2 is dealt from a server seed committed to as `"));
+
+    table.act(1, Action::Fold, 0).unwrap();
+    table.act(2, Action::Fold, 0).unwrap();
+    let results = table.results().unwrap();
+    assert!(results.starts_with("**Hand #1** results:\n<@3> wins 15 from the main pot\n"));
+
+    // The revealed seed deals the deck the hand was played with.
+    let shuffle = table.shuffle.clone().unwrap();
+    assert_eq!(shuffle.entropy, vec!["10", "1"]);
+    let seed = fair::to_hex(&shuffle.server_seed);
+    let commitment = fair::to_hex(&shuffle.commitment());
+    assert!(results.ends_with(&format!("`a!verify {} {} 10 1`", seed, commitment)));
+    let mut deck = fair::verify(&seed, &shuffle.entropy, &commitment).unwrap();
+    let dealt = table.game.as_ref().unwrap().players.get_player(2).unwrap().hand.unwrap();
+    // Player 2 is dealt the third and fourth cards.
+    deck.pop();
+    deck.pop();
+    assert_eq!(deck.pop(), Some(dealt.cards().0));
+
+    // The next hand mixes what the players added after its seed was
+    // committed to into the deck.
+    let next = table.next_shuffle.clone().unwrap();
+    assert!(table.add_entropy("my lucky 7"));
+    assert!(!table.add_entropy("  "));
+    table.deal().unwrap();
+    assert_eq!(table.game.as_ref().unwrap().hand_number, 2);
+    let dealt = table.shuffle.clone().unwrap();
+    assert_eq!(dealt.server_seed, next.server_seed);
+    assert_eq!(dealt.entropy, vec!["10", "2", "mylucky7"]);
+    assert_ne!(dealt.deck().cards(), next.deck().cards());
+    assert_ne!(table.next_shuffle.as_ref().unwrap().server_seed, dealt.server_seed);
+    assert_eq!(table.winner(), None);
+}
+
+#[test]
+fn winner() {
+    let mut table = table();
+    let game = table.game.as_mut().unwrap();
+    game.act(1, Action::AllIn).unwrap();
+    game.act(2, Action::Fold).unwrap();
+    game.act(3, Action::Fold).unwrap();
+    for id in [2, 3] {
+        game.players.get_player_mut(id).unwrap().money = 0;
+    }
+    assert_eq!(table.winner(), Some(1));
+    assert_eq!(table.deal(), Err(TableError::Game(GameError::NotEnoughPlayers)));
+}
+
+#[test]
+fn clock() {
+    let mut table = table();
+    assert_eq!(table.tick(1000), None);
+    assert_eq!(table.deadline(), Some(1090));
+    assert_eq!(table.tick(1079), None);
+    assert_eq!(table.tick(1080), Some(ClockEvent::Warning { player: 1, left: 10 }));
+    assert_eq!(table.tick(1081), None);
+
+    // Acting past the clock draws on the time bank.
+    table.act(1, Action::Call, 1045).unwrap();
+    assert_eq!(table.time_bank(1), 45);
+    assert_eq!(table.tick(1050), None);
+    assert_eq!(table.deadline(), Some(1050 + 30 + 60));
+
+    // Player 2 times out facing a bet and folds, player 3 can check.
+    assert_eq!(
+        table.tick(1140),
+        Some(ClockEvent::TimedOut { player: 2, action: Action::Fold, sat_out: false })
+    );
+    assert_eq!(table.time_bank(2), 0);
+    assert_eq!(table.tick(1140), None);
+    assert_eq!(
+        table.tick(1230),
+        Some(ClockEvent::TimedOut { player: 3, action: Action::Check, sat_out: false })
+    );
+    assert_eq!(table.game.as_ref().unwrap().street, Street::Flop);
+}
+
+#[test]
+fn sitting_out() {
+    let mut table = table();
+    table.tick(0);
+    table.act(1, Action::Call, 0).unwrap();
+    table.tick(0);
+    assert!(matches!(table.tick(90), Some(ClockEvent::TimedOut { player: 2, sat_out: false, .. })));
+    table.act(3, Action::Fold, 90).unwrap();
+
+    // Timing out twice in a row sits player 2 out, their time bank being empty.
+    table.deal().unwrap();
+    table.tick(200);
+    assert_eq!(
+        table.tick(230),
+        Some(ClockEvent::TimedOut { player: 2, action: Action::Fold, sat_out: true })
+    );
+    assert!(table.is_sitting_out(2));
+    assert!(table.status().contains("(button), sitting out"));
+    table.act(3, Action::Fold, 230).unwrap();
+
+    // From then on the clock acts for them as soon as it's their turn.
+    table.deal().unwrap();
+    table.act(3, Action::Call, 300).unwrap();
+    table.act(1, Action::Call, 300).unwrap();
+    assert_eq!(table.tick(300), Some(ClockEvent::SittingOut { player: 2, action: Action::Check }));
+
+    table.sit_in(2);
+    assert!(!table.is_sitting_out(2));
+}
+
+#[test]
+fn betting_structure() {
+    let settings = Settings { structure: Structure::PotLimit, ..Settings::default() };
+    let mut table = Table::open(1, 10, 1, settings);
+    table.join(2).unwrap();
+    table.join(3).unwrap();
+    table.start(1).unwrap();
+    assert!(table.status().ends_with("Waiting for <@1> to act, 10 to call, raise to 20 to 35."));
+}
+
+fn sit_and_go(settings: &str) -> Table {
+    let (settings, tournament) = Tournament::parse(settings).unwrap();
+    let mut table = Table::open(1, 10, 1, settings).with_tournament(tournament);
+    table.join(2).unwrap();
+    table.join(3).unwrap();
+    table.start(1).unwrap();
+    table
+}
+
+fn fold_around(table: &mut Table) {
+    let game = table.game.as_mut().unwrap();
+    while !game.over {
+        let id = game.current_player().unwrap().id;
+        game.act(id, Action::Fold).unwrap();
+    }
+}
+
+#[test]
+fn parse_tournament() {
+    let (settings, tournament) =
+        Tournament::parse("buyin=500 stack=2000 levels=10/20,20/40/5 every=15h payouts=50/30/20% seats=3-6").unwrap();
+    assert_eq!(settings.buy_in, 500);
+    assert_eq!(settings.blinds, Blinds::new(10, 20));
+    assert_eq!((settings.min_seats, settings.max_seats), (3, 6));
+    assert_eq!(tournament.stack, 2000);
+    assert_eq!(tournament.levels, vec![Blinds::new(10, 20), Blinds::new(20, 40).with_ante(5)]);
+    assert_eq!(tournament.length, LevelLength::Hands(15));
+    assert_eq!(tournament.to_string(), "sit-and-go, 2000 chips, 2 levels every 15 hands, pays 50/30/20%");
+
+    let (settings, tournament) = Tournament::parse("every=8min").unwrap();
+    assert_eq!(settings.blinds, Blinds::new(10, 20));
+    assert_eq!(tournament.length, LevelLength::Minutes(8));
+
+    assert_eq!(Tournament::parse("blinds=5/10"), Err(SettingsError::UnknownSetting(String::from("blinds"))));
+    assert_eq!(Tournament::parse("payouts=50/40"), Err(SettingsError::Payouts));
+    assert_eq!(Tournament::parse("buyin=100-500"), Err(SettingsError::BuyInRange));
+    assert_eq!(Tournament::parse("stack=10"), Err(SettingsError::Stack { big_blind: 20 }));
+    assert_eq!(Tournament::parse("every=0h"), Err(SettingsError::Levels));
+    assert_eq!(Tournament::parse("levels=20/10"), Err(SettingsError::Blinds));
+    assert_eq!(Tournament::parse("seats=1-4"), Err(SettingsError::Seats { min: 1, max: 4 }));
+
+    // The buy-in is checked against neither the default blinds nor the first
+    // level, the stack being separate from it.
+    let (settings, tournament) = Tournament::parse("buyin=3 stack=100 levels=1/2,2/4").unwrap();
+    assert_eq!((settings.buy_in, settings.blinds), (3, Blinds::new(1, 2)));
+    assert_eq!(tournament.stack, 100);
+    assert_eq!(Tournament::parse("buyin=1 levels=50/100").unwrap().0.blinds, Blinds::new(50, 100));
+    assert_eq!(
+        Tournament::parse("buyin=0"),
+        Err(SettingsError::InvalidValue { key: String::from("buyin"), value: String::from("0") })
+    );
+    assert_eq!(
+        Tournament::parse("every=5s"),
+        Err(SettingsError::InvalidValue { key: String::from("every"), value: String::from("5s") })
+    );
+}
+
+#[test]
+fn blind_levels() {
+    let mut table = sit_and_go("every=2h");
+    assert_eq!(table.game.as_ref().unwrap().players.get_player(1).unwrap().money, 1500);
+    assert!(table.status().contains("Level 1 of 10: blinds 10/20"));
+    fold_around(&mut table);
+    assert_eq!(table.level_up(0), None);
+    table.deal().unwrap();
+    fold_around(&mut table);
+    assert_eq!(table.level_up(0), Some(2));
+    assert_eq!(table.game.as_ref().unwrap().blinds, Blinds::new(15, 30));
+    assert_eq!(table.settings.blinds, Blinds::new(15, 30));
+    assert_eq!(table.level_up(0), None);
+
+    let mut tournament = Tournament { started: 1000, ..Tournament::default() };
+    assert_eq!(tournament.level_at(50, 1599), 0);
+    assert_eq!(tournament.level_at(0, 1600), 1);
+    assert_eq!(tournament.level_at(0, 100_000), 9);
+    tournament.length = LevelLength::Hands(5);
+    assert_eq!(tournament.level_at(12, 100_000), 2);
+}
+
+#[test]
+fn eliminations() {
+    let mut table = sit_and_go("");
+    fold_around(&mut table);
+    let game = table.game.as_mut().unwrap();
+    for (id, committed) in [(1, 1500), (2, 5)] {
+        let player = game.players.get_player_mut(id).unwrap();
+        player.money = 0;
+        player.committed = committed;
+    }
+
+    // Busting in the same hand, the bigger stack at the start of the hand
+    // finishes higher.
+    assert_eq!(table.eliminate(), vec![(2, 3), (1, 2)]);
+    assert_eq!(table.eliminate(), vec![]);
+    assert!(table.status().contains("<@2>: out in 3rd place"));
+
+    assert_eq!(table.standings(), Some(vec![3, 1, 2]));
+    assert_eq!(table.prize_pool(), 3000);
+    assert_eq!(table.cash_outs(), vec![(3, 1950), (1, 1050)]);
+}
+
+#[test]
+fn prizes() {
+    let tournament = Tournament { payouts: vec![50, 30, 20], ..Tournament::default() };
+    assert_eq!(tournament.prizes(5, 1001), vec![501, 300, 200]);
+    // Places nobody finished in go to the winner.
+    assert_eq!(tournament.prizes(2, 1000), vec![700, 300]);
+    assert_eq!(ordinal(1), "1st");
+    assert_eq!(ordinal(12), "12th");
+    assert_eq!(ordinal(23), "23rd");
+
+    // Unfinished tournaments give the buy-ins back.
+    let table = sit_and_go("buyin=200");
+    assert_eq!(table.cash_outs(), vec![(1, 200), (2, 200), (3, 200)]);
+}
+
+#[test]
+fn seat_and_unseat() {
+    let mut table = table();
+    assert_eq!(table.seat(4, 800), Ok(()));
+    assert_eq!(table.seat(4, 800), Err(TableError::Lobby(LobbyError::AlreadySeated)));
+    assert!(table.status().contains("<@4>: 800 chips, dealt in next hand"));
+    assert_eq!(table.unseat(1), Err(TableError::Game(GameError::HandInProgress)));
+
+    table.act(1, Action::Fold, 0).unwrap();
+    table.act(2, Action::Fold, 0).unwrap();
+    assert_eq!(table.unseat(1), Ok(1000));
+    assert_eq!(table.host, 2);
+    table.deal().unwrap();
+    assert_eq!(table.seats, vec![2, 3, 4]);
+    assert!(table.game.as_ref().unwrap().players.get_player(4).unwrap().hand.is_some());
+}
+
+// Cash table in channel 10 opened by player 1 with 1000 chips, buy-ins from
+// 200 to 1000.
+fn cash_table() -> Table {
+    Table::open(1, 10, 1, Settings::default()).with_cash(1000)
+}
+
+#[test]
+fn cash_table_sessions() {
+    let mut table = cash_table();
+    assert!(!table.can_deal());
+    assert!(table.status().starts_with("**Cash table open**: no-limit, buy-in 200 to 1000"));
+    assert!(table.status().ends_with("Waiting for more players, sit down with `a!join <chips>`."));
+    assert_eq!(table.sit_down(2, 100), Err(TableError::Lobby(LobbyError::BuyIn { min: 200, max: 1000 })));
+    assert_eq!(table.sit_down(1, 500), Err(TableError::Lobby(LobbyError::AlreadySeated)));
+    assert_eq!(Table::open(1, 11, 1, Settings::default()).check_buy_in(500), Err(LobbyError::NotCashTable));
+
+    // Players sit down between hands and in the middle of one.
+    table.sit_down(2, 500).unwrap();
+    assert!(table.can_deal());
+    table.deal().unwrap();
+    table.sit_down(3, 800).unwrap();
+    assert!(table.status().contains("<@3>: 800 chips, dealt in next hand"));
+    assert_eq!(table.stacks(), vec![(1, 1000), (2, 500), (3, 800)]);
+    table.act(1, Action::Fold, 0).unwrap();
+    assert_eq!(table.winner(), None);
+    table.deal().unwrap();
+    assert!(table.game.as_ref().unwrap().players.get_player(3).unwrap().is_playing);
+
+    // Players sitting out aren't dealt in, and one player left waits for more.
+    fold_around(&mut table);
+    table.sit_out(1);
+    table.sit_out(2);
+    assert!(!table.can_deal());
+    table.sit_in(2);
+    table.deal().unwrap();
+    let game = table.game.as_ref().unwrap();
+    assert!(!game.players.get_player(1).unwrap().is_playing);
+    assert_eq!(game.players.get_player(1).unwrap().money, table.stacks()[0].1);
+}
+
+#[test]
+fn cash_top_ups() {
+    let mut table = cash_table();
+    table.sit_down(2, 300).unwrap();
+    assert_eq!(table.top_up(1, 100), Err(TableError::Lobby(LobbyError::TopUp { max: 0 })));
+    assert_eq!(table.top_up(2, 800), Err(TableError::Lobby(LobbyError::TopUp { max: 700 })));
+    assert_eq!(table.top_up(4, 100), Err(TableError::Lobby(LobbyError::NotSeated)));
+
+    // Between hands the chips go on the stack right away, during one once
+    // it is over.
+    table.top_up(2, 200).unwrap();
+    table.deal().unwrap();
+    table.top_up(2, 100).unwrap();
+    assert!(table.status().contains(", topping up 100"));
+    assert_eq!(table.top_up(2, 500), Err(TableError::Lobby(LobbyError::TopUp { max: 400 })));
+    assert_eq!(table.stacks(), vec![(1, 1000), (2, 600)]);
+    fold_around(&mut table);
+    assert!(table.settle().is_empty());
+    let stacks = table.stacks();
+    assert_eq!(stacks.iter().map(|s| s.1).sum::<u32>(), 1600);
+
+    // Busted players rebuy for at least the smallest buy-in.
+    table.game.as_mut().unwrap().players.get_player_mut(2).unwrap().money = 0;
+    assert_eq!(table.top_up(2, 100), Err(TableError::Lobby(LobbyError::BuyIn { min: 200, max: 1000 })));
+    table.top_up(2, 200).unwrap();
+    assert_eq!(table.stacks()[1], (2, 200));
+
+    // Players who folded top up from what they have left, their blind is lost.
+    table.sit_down(3, 1000).unwrap();
+    table.deal().unwrap();
+    let game = table.game.as_mut().unwrap();
+    let first = game.current_player().unwrap().id;
+    game.act(first, Action::Call).unwrap();
+    let folded = game.current_player().unwrap().id;
+    game.act(folded, Action::Fold).unwrap();
+    assert!(!game.over);
+    let money = game.players.get_player(folded).unwrap().money;
+    assert_eq!(table.top_up(folded, 1000), Err(TableError::Lobby(LobbyError::TopUp { max: 1000 - money })));
+}
+
+#[test]
+fn cash_leaving() {
+    let mut table = cash_table();
+    table.sit_down(2, 500).unwrap();
+    table.sit_down(3, 500).unwrap();
+    assert_eq!(table.stand_up(4), Err(TableError::Lobby(LobbyError::NotSeated)));
+    table.deal().unwrap();
+    table.sit_down(4, 400).unwrap();
+
+    // Players not dealt in leave right away, the others once the hand is over.
+    assert_eq!(table.stand_up(4), Ok(Some(400)));
+    assert_eq!(table.stand_up(2), Ok(None));
+    assert!(table.status().contains("<@2>: 495 chips, leaving after this hand"));
+    assert!(table.settle().is_empty());
+    table.act(1, Action::Fold, 0).unwrap();
+    table.act(2, Action::Fold, 0).unwrap();
+    assert_eq!(table.settle(), vec![(2, 495)]);
+    assert_eq!(table.seats, vec![1, 3]);
+    assert!(!table.is_sitting_out(2));
+    assert_eq!(table.stand_up(1), Ok(Some(1000)));
+    assert_eq!(table.host, 3);
+    assert!(!table.can_deal());
+}
@@ -0,0 +1,317 @@
+// Sit-and-go tournaments: everyone pays the same buy-in into a prize pool and
+// starts with the same stack, the blinds go up level by level on a timer or
+// every few hands, and the last players standing share the pool. Opened with
+// the table settings plus its own, e.g.
+// `a!tournament buyin=500 stack=1500 levels=10/20,20/40,50/100/10 every=8m payouts=50/30/20`.
+use std::fmt;
+use std::str::FromStr;
+
+use serde::{Deserialize, Serialize};
+
+use super::*;
+use crate::poker::{Blinds, Player};
+
+// Places paid at most
+pub const MAX_PAYOUTS: usize = MAX_SEATS;
+
+#[derive(PartialEq, Eq, Clone, Copy, Debug, Serialize, Deserialize)]
+#[serde(tag = "every", content = "length", rename_all = "kebab-case")]
+pub enum LevelLength {
+    Minutes(u32),
+    Hands(u32),
+}
+
+impl FromStr for LevelLength {
+    type Err = ();
+
+    // `10m` or `10min` for minutes, `20h` or `20hands` for hands
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.to_lowercase();
+        let split = s.find(|c: char| !c.is_ascii_digit()).unwrap_or(s.len());
+        let (n, unit) = s.split_at(split);
+        let n = n.parse().map_err(|_| ())?;
+        match unit {
+            "m" | "min" | "mins" | "minutes" => Ok(LevelLength::Minutes(n)),
+            "h" | "hand" | "hands" => Ok(LevelLength::Hands(n)),
+            _ => Err(()),
+        }
+    }
+}
+
+impl fmt::Display for LevelLength {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            LevelLength::Minutes(1) => write!(f, "every minute"),
+            LevelLength::Minutes(n) => write!(f, "every {} minutes", n),
+            LevelLength::Hands(1) => write!(f, "every hand"),
+            LevelLength::Hands(n) => write!(f, "every {} hands", n),
+        }
+    }
+}
+
+#[derive(PartialEq, Eq, Clone, Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Tournament {
+    // Chips every player starts with, whatever the buy-in
+    pub stack: u32,
+    pub levels: Vec<Blinds>,
+    pub length: LevelLength,
+    // Percent of the prize pool paid to each place, first place first
+    pub payouts: Vec<u32>,
+    // Index into `levels` of the blinds being played
+    pub level: usize,
+    // When the game started, in seconds since the unix epoch
+    pub started: u64,
+    // Busted players, the first one out first
+    #[serde(with = "crate::poker::ids::list")]
+    pub eliminated: Vec<u64>,
+}
+
+impl Default for Tournament {
+    fn default() -> Self {
+        let levels = [
+            (10, 20, 0), (15, 30, 0), (25, 50, 0), (50, 100, 0), (75, 150, 0),
+            (100, 200, 25), (150, 300, 25), (200, 400, 50), (300, 600, 75), (500, 1000, 100),
+        ];
+        Tournament {
+            stack: 1500,
+            levels: levels.iter().map(|&(small, big, ante)| Blinds::new(small, big).with_ante(ante)).collect(),
+            length: LevelLength::Minutes(10),
+            payouts: vec![65, 35],
+            level: 0,
+            started: 0,
+            eliminated: Vec::new(),
+        }
+    }
+}
+
+impl Tournament {
+    // Parses the settings of a table running a tournament, its blinds coming
+    // from the first level.
+    pub fn parse(s: &str) -> Result<(Settings, Tournament), SettingsError> {
+        let mut tournament = Tournament::default();
+        let mut rest = Vec::new();
+        for setting in s.split_whitespace() {
+            let mut parts = setting.splitn(2, '=');
+            let key = parts.next().unwrap_or_default().to_lowercase();
+            match (key.as_str(), parts.next()) {
+                ("stack", Some(value)) | ("levels", Some(value)) | ("every", Some(value))
+                | ("payouts", Some(value)) => tournament.set(&key, value)?,
+                ("blinds", _) | ("ante", _) | ("bbante", _) => {
+                    return Err(SettingsError::UnknownSetting(key))
+                }
+                _ => rest.push(setting),
+            }
+        }
+        let mut settings = Settings::read(&rest.join(" "))?.fixed_buy_in()?;
+        tournament.validate()?;
+        settings.blinds = tournament.levels[0];
+        settings.validate_game()?;
+        Ok((settings, tournament))
+    }
+
+    fn set(&mut self, key: &str, value: &str) -> Result<(), SettingsError> {
+        let invalid = || SettingsError::InvalidValue {
+            key: String::from(key),
+            value: String::from(value),
+        };
+        match key {
+            "stack" => self.stack = value.parse().map_err(|_| invalid())?,
+            "levels" => {
+                self.levels = value
+                    .split(',')
+                    .map(|level| {
+                        let amounts = level
+                            .split('/')
+                            .map(|n| n.parse::<u32>())
+                            .collect::<Result<Vec<u32>, _>>()
+                            .map_err(|_| invalid())?;
+                        match amounts.as_slice() {
+                            [small, big] => Ok(Blinds::new(*small, *big)),
+                            [small, big, ante] => Ok(Blinds::new(*small, *big).with_ante(*ante)),
+                            _ => Err(invalid()),
+                        }
+                    })
+                    .collect::<Result<_, _>>()?;
+            }
+            "every" => self.length = value.parse().map_err(|_| invalid())?,
+            "payouts" => {
+                self.payouts = value
+                    .split('/')
+                    .map(|n| n.trim_end_matches('%').parse())
+                    .collect::<Result<_, _>>()
+                    .map_err(|_| invalid())?;
+            }
+            _ => return Err(SettingsError::UnknownSetting(String::from(key))),
+        }
+        Ok(())
+    }
+
+    fn validate(&self) -> Result<(), SettingsError> {
+        let length = match self.length {
+            LevelLength::Minutes(n) | LevelLength::Hands(n) => n,
+        };
+        if self.levels.is_empty() || length == 0 {
+            return Err(SettingsError::Levels);
+        }
+        if self.levels.iter().any(|b| b.small == 0 || b.small > b.big) {
+            return Err(SettingsError::Blinds);
+        }
+        if self.stack < self.levels[0].big {
+            return Err(SettingsError::Stack { big_blind: self.levels[0].big });
+        }
+        let total: u32 = self.payouts.iter().sum();
+        if self.payouts.len() > MAX_PAYOUTS || self.payouts.contains(&0) || total != 100 {
+            return Err(SettingsError::Payouts);
+        }
+        Ok(())
+    }
+
+    pub fn blinds(&self) -> Blinds {
+        self.levels[self.level.min(self.levels.len() - 1)]
+    }
+
+    // Level to play after `hands` hands, `now` in seconds since the unix epoch.
+    // The last level lasts until the end.
+    pub fn level_at(&self, hands: u32, now: u64) -> usize {
+        let level = match self.length {
+            LevelLength::Minutes(n) => (now.saturating_sub(self.started) / (n as u64 * 60)) as usize,
+            LevelLength::Hands(n) => (hands / n) as usize,
+        };
+        level.min(self.levels.len() - 1)
+    }
+
+    // Prize of each place out of a pool shared by `entrants`, first place
+    // first. The share of places nobody finished in and the chips lost to
+    // rounding go to the winner.
+    pub fn prizes(&self, entrants: usize, pool: u64) -> Vec<u64> {
+        let mut prizes: Vec<u64> = self
+            .payouts
+            .iter()
+            .take(entrants)
+            .map(|&percent| pool * percent as u64 / 100)
+            .collect();
+        let paid: u64 = prizes.iter().sum();
+        if let Some(first) = prizes.first_mut() {
+            *first += pool - paid;
+        }
+        prizes
+    }
+}
+
+impl fmt::Display for Tournament {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let payouts: Vec<String> = self.payouts.iter().map(|p| p.to_string()).collect();
+        write!(
+            f,
+            "sit-and-go, {} chips, {} levels {}, pays {}%",
+            self.stack,
+            self.levels.len(),
+            self.length,
+            payouts.join("/"),
+        )
+    }
+}
+
+impl Table {
+    pub fn with_tournament(mut self, tournament: Tournament) -> Self {
+        self.tournament = Some(tournament);
+        self
+    }
+
+    // Moves to the level the schedule is at before a hand is dealt, returning
+    // the new level's number when the blinds went up.
+    pub fn level_up(&mut self, now: u64) -> Option<usize> {
+        let game = self.game.as_mut()?;
+        let tournament = self.tournament.as_mut()?;
+        let level = tournament.level_at(game.hand_number, now);
+        if level <= tournament.level {
+            return None;
+        }
+        tournament.level = level;
+        game.blinds = tournament.blinds();
+        self.settings.blinds = game.blinds;
+        Some(level + 1)
+    }
+
+    // Players a finished hand busted and who aren't out yet, the one who
+    // started the hand with fewer chips first.
+    pub fn busted(&self) -> Vec<u64> {
+        let game = match self.game.as_ref().filter(|g| g.over) {
+            Some(game) => game,
+            None => return Vec::new(),
+        };
+        let out = self.tournament.as_ref().map(|t| t.eliminated.as_slice()).unwrap_or_default();
+        let mut busted: Vec<&Player> = game
+            .players
+            .iter()
+            .filter(|p| p.money == 0 && !out.contains(&p.id))
+            .collect();
+        busted.sort_by_key(|p| p.committed);
+        busted.into_iter().map(|p| p.id).collect()
+    }
+
+    // Eliminates the players a finished hand busted, returning them with the
+    // place they finished in. Of the players busted in the same hand, the one
+    // who started it with more chips finishes higher.
+    pub fn eliminate(&mut self) -> Vec<(u64, usize)> {
+        let busted = self.busted();
+        let (game, tournament) = match (&self.game, self.tournament.as_mut()) {
+            (Some(game), Some(tournament)) => (game, tournament),
+            _ => return Vec::new(),
+        };
+        let entrants = game.players.iter().count();
+        busted
+            .into_iter()
+            .map(|id| {
+                tournament.eliminated.push(id);
+                (id, entrants + 1 - tournament.eliminated.len())
+            })
+            .collect()
+    }
+
+    // Players by the place they finished in once the tournament is over
+    pub fn standings(&self) -> Option<Vec<u64>> {
+        let tournament = self.tournament.as_ref()?;
+        let winner = self.winner()?;
+        Some(std::iter::once(winner).chain(tournament.eliminated.iter().rev().copied()).collect())
+    }
+
+    // Prize pool of the buy-ins
+    pub fn prize_pool(&self) -> u64 {
+        let entrants = match &self.game {
+            Some(game) => game.players.iter().count(),
+            None => self.seats.len(),
+        };
+        entrants as u64 * self.settings.buy_in as u64
+    }
+
+    // Chips each player gets back in their wallet if the table closed now. A
+    // finished tournament pays its prizes and one that didn't finish gives
+    // everyone their buy-in back, other tables give players their stacks.
+    pub fn cash_outs(&self) -> Vec<(u64, u64)> {
+        let (tournament, game) = match (&self.tournament, &self.game) {
+            (Some(tournament), Some(game)) => (tournament, game),
+            _ => return self.stacks().into_iter().map(|(id, chips)| (id, chips as u64)).collect(),
+        };
+        match self.standings() {
+            Some(standings) => {
+                let prizes = tournament.prizes(standings.len(), self.prize_pool());
+                standings.into_iter().zip(prizes).collect()
+            }
+            None => game.players.iter().map(|p| (p.id, self.settings.buy_in as u64)).collect(),
+        }
+    }
+}
+
+// "1st", "2nd", "3rd", "4th"...
+pub fn ordinal(place: usize) -> String {
+    let suffix = match (place % 10, place % 100) {
+        (_, 11..=13) => "th",
+        (1, _) => "st",
+        (2, _) => "nd",
+        (3, _) => "rd",
+        _ => "th",
+    };
+    format!("{}{}", place, suffix)
+}
@@ -0,0 +1,276 @@
+// Chips that carry over between games: every user has a balance per guild,
+// and every change to it is an entry of the guild's ledger. Only the ledger is
+// stored, balances are rebuilt from it when it's loaded, and each entry
+// records the balance it left so a ledger that was tampered with or lost an
+// entry is refused.
+use std::collections::HashMap;
+use std::convert::TryFrom;
+use std::fmt;
+use std::io;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use serde::{Deserialize, Serialize};
+
+use crate::store::Store;
+
+#[cfg(test)]
+mod test;
+
+#[derive(PartialEq, Eq, Clone, Copy, Debug, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum Kind {
+    Grant {
+        #[serde(with = "crate::poker::ids")]
+        by: u64,
+    },
+    Revoke {
+        #[serde(with = "crate::poker::ids")]
+        by: u64,
+    },
+    BuyIn {
+        #[serde(with = "crate::poker::ids")]
+        channel: u64,
+    },
+    CashOut {
+        #[serde(with = "crate::poker::ids")]
+        channel: u64,
+    },
+}
+
+impl Kind {
+    fn is_credit(&self) -> bool {
+        matches!(self, Kind::Grant { .. } | Kind::CashOut { .. })
+    }
+}
+
+impl fmt::Display for Kind {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Kind::Grant { by } => write!(f, "granted by <@{}>", by),
+            Kind::Revoke { by } => write!(f, "revoked by <@{}>", by),
+            Kind::BuyIn { channel } => write!(f, "bought in at <#{}>", channel),
+            Kind::CashOut { channel } => write!(f, "cashed out from <#{}>", channel),
+        }
+    }
+}
+
+#[derive(PartialEq, Eq, Clone, Debug, Serialize, Deserialize)]
+pub struct Transaction {
+    pub id: u64,
+    #[serde(with = "crate::poker::ids")]
+    pub user: u64,
+    #[serde(flatten)]
+    pub kind: Kind,
+    pub amount: u64,
+    // Balance of the user after the transaction
+    pub balance: u64,
+    // Seconds since the unix epoch
+    pub time: u64,
+}
+
+#[derive(Debug)]
+pub enum WalletError {
+    ZeroAmount,
+    InsufficientFunds { balance: u64 },
+    Overflow { balance: u64 },
+    Io(io::Error),
+}
+
+impl fmt::Display for WalletError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            WalletError::ZeroAmount =>
+                write!(f, "the amount must be at least 1 chip"),
+            WalletError::InsufficientFunds { balance } =>
+                write!(f, "the wallet only has {} chips", balance),
+            WalletError::Overflow { balance } =>
+                write!(f, "the wallet already has {} chips and can't hold that many more", balance),
+            WalletError::Io(why) =>
+                write!(f, "the wallet couldn't be saved: {}", why),
+        }
+    }
+}
+
+impl std::error::Error for WalletError {}
+
+impl From<io::Error> for WalletError {
+    fn from(why: io::Error) -> Self {
+        WalletError::Io(why)
+    }
+}
+
+// Wallets of the users of a guild
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(into = "Ledger", try_from = "Ledger")]
+pub struct Wallet {
+    guild: u64,
+    ledger: Vec<Transaction>,
+    balances: HashMap<u64, u64>,
+}
+
+impl Wallet {
+    pub fn new(guild: u64) -> Self {
+        Wallet {
+            guild,
+            ledger: Vec::new(),
+            balances: HashMap::new(),
+        }
+    }
+
+    pub fn guild(&self) -> u64 {
+        self.guild
+    }
+
+    pub fn balance(&self, user: u64) -> u64 {
+        self.balances.get(&user).copied().unwrap_or(0)
+    }
+
+    pub fn grant(&mut self, user: u64, amount: u64, by: u64) -> Result<&Transaction, WalletError> {
+        self.record(user, Kind::Grant { by }, amount)
+    }
+
+    pub fn revoke(&mut self, user: u64, amount: u64, by: u64) -> Result<&Transaction, WalletError> {
+        self.record(user, Kind::Revoke { by }, amount)
+    }
+
+    // Takes the chips brought to the table at `channel`
+    pub fn buy_in(&mut self, user: u64, amount: u64, channel: u64) -> Result<&Transaction, WalletError> {
+        self.record(user, Kind::BuyIn { channel }, amount)
+    }
+
+    // Returns the chips taken from the table at `channel`
+    pub fn cash_out(&mut self, user: u64, amount: u64, channel: u64) -> Result<&Transaction, WalletError> {
+        self.record(user, Kind::CashOut { channel }, amount)
+    }
+
+    fn record(&mut self, user: u64, kind: Kind, amount: u64) -> Result<&Transaction, WalletError> {
+        if amount == 0 {
+            return Err(WalletError::ZeroAmount);
+        }
+        let balance = self.balance(user);
+        let balance = if kind.is_credit() {
+            balance.checked_add(amount).ok_or(WalletError::Overflow { balance })?
+        } else if balance >= amount {
+            balance - amount
+        } else {
+            return Err(WalletError::InsufficientFunds { balance });
+        };
+        let time = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_secs())
+            .unwrap_or(0);
+        self.balances.insert(user, balance);
+        self.ledger.push(Transaction {
+            id: self.ledger.len() as u64 + 1,
+            user,
+            kind,
+            amount,
+            balance,
+            time,
+        });
+        Ok(&self.ledger[self.ledger.len() - 1])
+    }
+
+    // Every transaction of the guild, oldest first
+    pub fn ledger(&self) -> &[Transaction] {
+        &self.ledger
+    }
+
+    // Transactions of a user, newest first
+    pub fn history(&self, user: u64) -> impl Iterator<Item = &Transaction> {
+        self.ledger.iter().rev().filter(move |t| t.user == user)
+    }
+}
+
+// How a Wallet is stored: the guild and its ledger, balances are replayed.
+#[derive(Serialize, Deserialize)]
+struct Ledger {
+    #[serde(with = "crate::poker::ids")]
+    guild: u64,
+    ledger: Vec<Transaction>,
+}
+
+impl From<Wallet> for Ledger {
+    fn from(wallet: Wallet) -> Self {
+        Ledger {
+            guild: wallet.guild,
+            ledger: wallet.ledger,
+        }
+    }
+}
+
+impl TryFrom<Ledger> for Wallet {
+    type Error = String;
+
+    fn try_from(ledger: Ledger) -> Result<Self, Self::Error> {
+        let mut balances: HashMap<u64, u64> = HashMap::new();
+        for (i, t) in ledger.ledger.iter().enumerate() {
+            if t.id != i as u64 + 1 {
+                return Err(format!("transaction {} is out of sequence", t.id));
+            }
+            let balance = balances.entry(t.user).or_insert(0);
+            let after = if t.kind.is_credit() {
+                balance.checked_add(t.amount)
+            } else {
+                balance.checked_sub(t.amount)
+            };
+            if after != Some(t.balance) {
+                return Err(format!("transaction {} doesn't match the balance", t.id));
+            }
+            *balance = t.balance;
+        }
+        Ok(Wallet {
+            guild: ledger.guild,
+            ledger: ledger.ledger,
+            balances,
+        })
+    }
+}
+
+// The wallets of every guild, loaded when first used and saved on every
+// transaction.
+pub struct Wallets {
+    store: Store,
+    wallets: HashMap<u64, Wallet>,
+}
+
+impl Wallets {
+    pub fn new(store: Store) -> Self {
+        Wallets {
+            store,
+            wallets: HashMap::new(),
+        }
+    }
+
+    pub fn get(&mut self, guild: u64) -> io::Result<&Wallet> {
+        self.load(guild).map(|w| &*w)
+    }
+
+    // Runs a transaction on the wallet of a guild and saves it, the
+    // transaction is rolled back if saving fails.
+    pub fn transact<F>(&mut self, guild: u64, f: F) -> Result<Transaction, WalletError>
+    where
+        F: FnOnce(&mut Wallet) -> Result<&Transaction, WalletError>,
+    {
+        let wallet = self.load(guild)?;
+        let before = wallet.clone();
+        let transaction = f(wallet)?.clone();
+        if let Err(why) = self.store.save(guild, &self.wallets[&guild]) {
+            self.wallets.insert(guild, before);
+            return Err(why.into());
+        }
+        Ok(transaction)
+    }
+
+    fn load(&mut self, guild: u64) -> io::Result<&mut Wallet> {
+        if !self.wallets.contains_key(&guild) {
+            let wallet = match self.store.load(guild) {
+                Ok(wallet) => wallet,
+                Err(why) if why.kind() == io::ErrorKind::NotFound => Wallet::new(guild),
+                Err(why) => return Err(why),
+            };
+            self.wallets.insert(guild, wallet);
+        }
+        Ok(self.wallets.get_mut(&guild).unwrap())
+    }
+}
@@ -0,0 +1,108 @@
+use std::fs;
+use std::process;
+
+use super::*;
+
+fn store(name: &str) -> Store {
+    let dir = std::env::temp_dir().join(format!("poker-wallets-{}-{}", name, process::id()));
+    Store::open(dir).unwrap()
+}
+
+fn fresh_store(name: &str) -> Store {
+    let dir = std::env::temp_dir().join(format!("poker-wallets-{}-{}", name, process::id()));
+    let _ = fs::remove_dir_all(&dir);
+    Store::open(dir).unwrap()
+}
+
+#[test]
+fn transactions() {
+    let mut wallet = Wallet::new(1);
+    assert_eq!(wallet.balance(10), 0);
+    assert_eq!(wallet.grant(10, 5000, 99).unwrap().balance, 5000);
+    assert_eq!(wallet.buy_in(10, 1000, 7).unwrap().balance, 4000);
+    assert_eq!(wallet.cash_out(10, 1800, 7).unwrap().balance, 5800);
+    assert_eq!(wallet.revoke(10, 800, 99).unwrap().balance, 5000);
+    assert_eq!(wallet.balance(10), 5000);
+
+    let ids: Vec<u64> = wallet.ledger().iter().map(|t| t.id).collect();
+    assert_eq!(ids, vec![1, 2, 3, 4]);
+    assert_eq!(wallet.ledger()[1].kind, Kind::BuyIn { channel: 7 });
+}
+
+#[test]
+fn insufficient_funds() {
+    let mut wallet = Wallet::new(1);
+    wallet.grant(10, 500, 99).unwrap();
+    assert!(matches!(
+        wallet.buy_in(10, 1000, 7),
+        Err(WalletError::InsufficientFunds { balance: 500 })
+    ));
+    assert!(matches!(
+        wallet.revoke(20, 1, 99),
+        Err(WalletError::InsufficientFunds { balance: 0 })
+    ));
+    assert!(matches!(wallet.grant(10, 0, 99), Err(WalletError::ZeroAmount)));
+    assert_eq!(wallet.ledger().len(), 1);
+    assert_eq!(wallet.balance(10), 500);
+}
+
+#[test]
+fn overflow() {
+    let mut wallet = Wallet::new(1);
+    wallet.grant(10, u64::MAX - 5, 99).unwrap();
+    assert!(matches!(
+        wallet.cash_out(10, 6, 7),
+        Err(WalletError::Overflow { balance }) if balance == u64::MAX - 5
+    ));
+    assert!(matches!(wallet.grant(10, u64::MAX, 99), Err(WalletError::Overflow { .. })));
+    wallet.cash_out(10, 5, 7).unwrap();
+    assert_eq!(wallet.ledger().len(), 2);
+    assert_eq!(wallet.balance(10), u64::MAX);
+}
+
+#[test]
+fn history() {
+    let mut wallet = Wallet::new(1);
+    wallet.grant(10, 500, 99).unwrap();
+    wallet.grant(20, 300, 99).unwrap();
+    wallet.buy_in(10, 200, 7).unwrap();
+    let history: Vec<u64> = wallet.history(10).map(|t| t.id).collect();
+    assert_eq!(history, vec![3, 1]);
+}
+
+#[test]
+fn ledger_is_audited() {
+    let mut wallet = Wallet::new(381249562934312961);
+    wallet.grant(10, 500, 99).unwrap();
+    wallet.buy_in(10, 200, 7).unwrap();
+    let json = serde_json::to_string(&wallet).unwrap();
+    assert!(json.contains(r#""guild":"381249562934312961""#));
+    assert!(json.contains(r#""kind":"buy_in","channel":"7""#));
+    assert!(!json.contains("balances"));
+
+    let loaded: Wallet = serde_json::from_str(&json).unwrap();
+    assert_eq!(loaded.balance(10), 300);
+    assert_eq!(loaded.ledger(), wallet.ledger());
+
+    // A changed amount no longer adds up to the recorded balance.
+    let tampered = json.replace(r#""amount":200"#, r#""amount":100"#);
+    assert!(serde_json::from_str::<Wallet>(&tampered).is_err());
+
+    // Neither does a ledger missing an entry.
+    let mut value: serde_json::Value = serde_json::from_str(&json).unwrap();
+    value["ledger"].as_array_mut().unwrap().remove(0);
+    assert!(serde_json::from_value::<Wallet>(value).is_err());
+}
+
+#[test]
+fn wallets_are_saved() {
+    let mut wallets = Wallets::new(fresh_store("saved"));
+    wallets.transact(1, |w| w.grant(10, 500, 99)).unwrap();
+    wallets.transact(2, |w| w.grant(10, 50, 99)).unwrap();
+    assert!(wallets.transact(1, |w| w.buy_in(10, 600, 7)).is_err());
+
+    let mut restarted = Wallets::new(store("saved"));
+    assert_eq!(restarted.get(1).unwrap().balance(10), 500);
+    assert_eq!(restarted.get(2).unwrap().balance(10), 50);
+    assert_eq!(restarted.get(3).unwrap().balance(10), 0);
+}